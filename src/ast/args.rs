@@ -9,7 +9,7 @@ pub enum Args<'a> {
 }
 
 pub trait ArgsVisitor<T> {
-    fn visit_args_expression_list(&self, expressions: &Vec<Expression>) -> T;
+    fn visit_args_expression_list(&self, expressions: &[Expression]) -> T;
     fn visit_args_table_constructor(&self, table_constructor: &TableConstructor) -> T;
     fn visit_args_literal_string(&self, token: &Token) -> T;
 }
@@ -0,0 +1,86 @@
+use crate::token::{Token, TokenType};
+
+/// Value computed at compile time, eg: by constant folding.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Constant {
+    Nil,
+    Boolean(bool),
+    Integer(i64),
+    Float(f64),
+    String(String),
+}
+
+impl Constant {
+    /// Converts literal token into a constant. Returns `None` for literals which value
+    /// cannot be reproduced exactly yet, eg: long strings, strings with escape sequences
+    /// or hex floats.
+    pub fn from_literal(token: &Token) -> Option<Constant> {
+        match token.token_type {
+            TokenType::Nil => Some(Constant::Nil),
+            TokenType::True => Some(Constant::Boolean(true)),
+            TokenType::False => Some(Constant::Boolean(false)),
+            TokenType::Numeral => Constant::from_numeral(token.lexeme),
+            TokenType::LiteralString => {
+                let literal = token.literal?;
+                // Scanner doesn't produce exact contents of long strings yet.
+                if token.lexeme.starts_with('[') || literal.contains('\\') {
+                    None
+                } else {
+                    Some(Constant::String(String::from(literal)))
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Converts numeral following the rules of `l_str2int` and `l_str2d`: decimal
+    /// integers which overflow become floats, hex integers wrap around.
+    fn from_numeral(numeral: &str) -> Option<Constant> {
+        if let Some(digits) = numeral
+            .strip_prefix("0x")
+            .or_else(|| numeral.strip_prefix("0X"))
+        {
+            if digits.is_empty() || !digits.chars().all(|char| char.is_ascii_hexdigit()) {
+                return None;
+            }
+
+            let value = digits.chars().fold(0u64, |value, char| {
+                value.wrapping_mul(16) + char.to_digit(16).unwrap() as u64
+            });
+            return Some(Constant::Integer(value as i64));
+        }
+
+        if numeral.chars().all(|char| char.is_ascii_digit()) {
+            if let Ok(value) = numeral.parse::<i64>() {
+                return Some(Constant::Integer(value));
+            }
+        }
+
+        numeral.parse::<f64>().ok().map(Constant::Float)
+    }
+
+    pub fn is_falsy(&self) -> bool {
+        matches!(self, Constant::Nil | Constant::Boolean(false))
+    }
+
+    pub fn to_float(&self) -> Option<f64> {
+        match self {
+            Constant::Integer(value) => Some(*value as f64),
+            Constant::Float(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Converts number to integer if it has an exact integer representation.
+    pub fn to_integer(&self) -> Option<i64> {
+        match self {
+            Constant::Integer(value) => Some(*value),
+            Constant::Float(value)
+                if value.fract() == 0.0 && *value >= -(2f64.powi(63)) && *value < 2f64.powi(63) =>
+            {
+                Some(*value as i64)
+            }
+            _ => None,
+        }
+    }
+}
@@ -1,17 +1,20 @@
-use super::{prefix::Prefix, PrefixVisitor, TableConstructor, TableConstructorVisitor};
-use crate::token::Token;
+use super::{prefix::Prefix, Constant, PrefixVisitor, TableConstructor, TableConstructorVisitor};
+use crate::token::{Token, TokenType};
 
 #[derive(Debug)]
 pub enum Expression<'e> {
     Literal(&'e Token<'e>),
+    Constant(Constant),
     Unary {
         operator: &'e Token<'e>,
         right: Box<Expression<'e>>,
     },
+    /// Chain of binary operators of the same precedence, eg: `1 - 2 + 3`, grouped as
+    /// given by [`is_right_associative`]. Chains are kept flat, so that their length
+    /// doesn't add to the depth of the tree.
     Binary {
-        left: Box<Expression<'e>>,
-        operator: &'e Token<'e>,
-        right: Box<Expression<'e>>,
+        first: Box<Expression<'e>>,
+        rest: Vec<(&'e Token<'e>, Expression<'e>)>,
     },
     TableConstructor(TableConstructor<'e>),
     Prefix(Prefix<'e>),
@@ -19,8 +22,9 @@ pub enum Expression<'e> {
 
 pub trait ExpressionVisitor<T>: PrefixVisitor<T> + TableConstructorVisitor<T> {
     fn visit_literal(&self, token: &Token) -> T;
-    fn visit_unary(&self, operator: &Token, right: &Box<Expression>) -> T;
-    fn visit_binary(&self, left: &Box<Expression>, operator: &Token, right: &Box<Expression>) -> T;
+    fn visit_constant(&self, constant: &Constant) -> T;
+    fn visit_unary(&self, operator: &Token, right: &Expression) -> T;
+    fn visit_binary(&self, first: &Expression, rest: &[(&Token, Expression)]) -> T;
     fn visit_table_constructor(&self, table_constructor: &TableConstructor) -> T {
         table_constructor.visit(self)
     }
@@ -29,19 +33,43 @@ pub trait ExpressionVisitor<T>: PrefixVisitor<T> + TableConstructorVisitor<T> {
     }
 }
 
+/// Whether chains of `operator` are grouped from the right, eg: `a .. b .. c` as
+/// `a .. (b .. c)`. `and` and `or` give the same result either way.
+pub fn is_right_associative(operator: &Token) -> bool {
+    matches!(
+        operator.token_type,
+        TokenType::Or | TokenType::And | TokenType::DotDot | TokenType::Caret
+    )
+}
+
 impl<'a> Expression<'a> {
+    /// Moves expression out, leaving `nil` constant in its place.
+    pub fn take(&mut self) -> Expression<'a> {
+        std::mem::replace(self, Expression::Constant(Constant::Nil))
+    }
+
+    /// Builds binary chain out of `first` and `rest`, or returns `first` when there
+    /// are no operators.
+    pub fn chain(first: Expression<'a>, rest: Vec<(&'a Token<'a>, Expression<'a>)>) -> Self {
+        if rest.is_empty() {
+            first
+        } else {
+            Expression::Binary {
+                first: Box::new(first),
+                rest,
+            }
+        }
+    }
+
     pub fn visit<T, V>(&self, visitor: &V) -> T
     where
         V: ExpressionVisitor<T>,
     {
         match self {
             Expression::Literal(token) => visitor.visit_literal(token),
+            Expression::Constant(constant) => visitor.visit_constant(constant),
             Expression::Unary { operator, right } => visitor.visit_unary(operator, right),
-            Expression::Binary {
-                left,
-                operator,
-                right,
-            } => visitor.visit_binary(left, operator, right),
+            Expression::Binary { first, rest } => visitor.visit_binary(first, rest),
             Expression::TableConstructor(table_constructor) => {
                 visitor.visit_table_constructor(table_constructor)
             }
@@ -14,8 +14,8 @@ pub enum FunctionCall<'a> {
 }
 
 pub trait FunctionCallVisitor<T> {
-    fn visit_function_call(&self, callee: &Box<Prefix>, args: &Args) -> T;
-    fn visit_method_call(&self, callee: &Box<Prefix>, method: &Identifier, args: &Args) -> T;
+    fn visit_function_call(&self, callee: &Prefix, args: &Args) -> T;
+    fn visit_method_call(&self, callee: &Prefix, method: &Identifier, args: &Args) -> T;
 }
 
 impl<'a> FunctionCall<'a> {
@@ -1,4 +1,5 @@
 pub mod args;
+pub mod constant;
 pub mod expression;
 pub mod field;
 pub mod function_call;
@@ -8,6 +9,7 @@ pub mod table_constructor;
 pub mod variable;
 
 pub use args::*;
+pub use constant::*;
 pub use expression::*;
 pub use field::*;
 pub use function_call::*;
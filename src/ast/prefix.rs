@@ -11,7 +11,7 @@ pub trait PrefixVisitor<T>: VariableVisitor<T> + FunctionCallVisitor<T> {
     fn visit_prefix_variable(&self, variable: &Variable) -> T {
         variable.visit(self)
     }
-    fn visit_prefix_group(&self, expression: &Box<Expression>) -> T;
+    fn visit_prefix_group(&self, expression: &Expression) -> T;
 }
 
 impl<'a> Prefix<'a> {
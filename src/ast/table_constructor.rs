@@ -6,7 +6,7 @@ pub struct TableConstructor<'a> {
 }
 
 pub trait TableConstructorVisitor<T>: FieldVisitor<T> {
-    fn visit_fields(&self, fields: &Vec<Field>) -> T;
+    fn visit_fields(&self, fields: &[Field]) -> T;
 }
 
 impl<'a> TableConstructor<'a> {
@@ -15,12 +15,9 @@ pub enum Variable<'a> {
 
 pub trait VariableVisitor<T> {
     fn visit_variable_identifier(&self, identifier: &Identifier) -> T;
-    fn visit_variable_member_access(&self, reference: &Box<Prefix>, member: &Identifier) -> T;
-    fn visit_variable_expression_member_access(
-        &self,
-        reference: &Box<Prefix>,
-        member: &Box<Expression>,
-    ) -> T;
+    fn visit_variable_member_access(&self, reference: &Prefix, member: &Identifier) -> T;
+    fn visit_variable_expression_member_access(&self, reference: &Prefix, member: &Expression)
+        -> T;
 }
 
 impl<'a> Variable<'a> {
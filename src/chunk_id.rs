@@ -0,0 +1,67 @@
+/// Maximum size of a chunk id, including the terminating `\0` (`LUA_IDSIZE`).
+const ID_SIZE: usize = 60;
+
+/// Port of `luaO_chunkid` from the reference implementation.
+pub(crate) fn chunk_id(chunk_name: &str) -> String {
+    if let Some(name) = chunk_name.strip_prefix('=') {
+        String::from(truncate_end(name, ID_SIZE - 1))
+    } else if let Some(file_name) = chunk_name.strip_prefix('@') {
+        if file_name.len() < ID_SIZE {
+            String::from(file_name)
+        } else {
+            format!("...{}", truncate_start(file_name, ID_SIZE - 4))
+        }
+    } else {
+        // Room left after `[string "`, `..."]` and `\0`.
+        let max_len = ID_SIZE - 15;
+        let first_line = chunk_name.split('\n').next().unwrap_or("");
+
+        if chunk_name.len() < max_len && first_line.len() == chunk_name.len() {
+            format!("[string \"{}\"]", chunk_name)
+        } else {
+            format!("[string \"{}...\"]", truncate_end(first_line, max_len))
+        }
+    }
+}
+
+fn truncate_end(text: &str, max_len: usize) -> &str {
+    let mut end = max_len.min(text.len());
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    &text[..end]
+}
+
+fn truncate_start(text: &str, max_len: usize) -> &str {
+    let mut start = text.len().saturating_sub(max_len);
+    while !text.is_char_boundary(start) {
+        start += 1;
+    }
+    &text[start..]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_format_chunk_id() {
+        assert_eq!(chunk_id("=stdin"), "stdin");
+        assert_eq!(chunk_id("@main.lua"), "main.lua");
+        assert_eq!(chunk_id("return 1"), "[string \"return 1\"]");
+        assert_eq!(chunk_id("foo\nbar"), "[string \"foo...\"]");
+        assert_eq!(chunk_id(&format!("={}", "a".repeat(100))), "a".repeat(59));
+        assert_eq!(
+            chunk_id(&format!("@{}.lua", "d/".repeat(50))),
+            format!("...{}.lua", "d/".repeat(26))
+        );
+        assert_eq!(
+            chunk_id(&"x".repeat(45)),
+            format!("[string \"{}...\"]", "x".repeat(45))
+        );
+        assert_eq!(
+            chunk_id(&"x".repeat(44)),
+            format!("[string \"{}\"]", "x".repeat(44))
+        );
+    }
+}
@@ -0,0 +1,133 @@
+//! Lua 5.4 scanner and parser.
+//!
+//! Source is turned into [`Token`]s by the [`Scanner`], which the [`Parser`] turns into
+//! an [`ast::Expression`]. The AST is consumed through the visitor traits in [`ast`],
+//! eg: [`visitor::debug_visitor::DebugVisitor`].
+//!
+//! With the `stdlib` feature, `pattern`, `math` and `utf8` port the parts of the string,
+//! math and utf8 libraries which don't need a runtime.
+
+pub mod ast;
+mod chunk_id;
+#[cfg(feature = "stdlib")]
+pub mod math;
+pub mod parser;
+#[cfg(feature = "stdlib")]
+pub mod pattern;
+pub mod scanner;
+pub mod token;
+#[cfg(feature = "transform")]
+pub mod transform;
+#[cfg(feature = "stdlib")]
+pub mod utf8;
+pub mod visitor;
+
+pub use parser::{Parser, ParsingError, ParsingResult};
+pub use scanner::{Scanner, ScanningError};
+pub use token::{Token, TokenType};
+
+use ast::ExpressionVisitor;
+
+/// Scans and parses `source`, then visits the resulting AST with `visitor`.
+///
+/// Errors are returned as messages formatted like in the reference implementation, eg:
+/// `[string "foo(1"]:1: Expected ')' after arguments list near <eof>`. Messages about
+/// unfinished strings and malformed numbers match `llex.c`, most others are our own.
+pub fn parse_chunk<T, V>(source: &str, visitor: &V) -> Result<T, String>
+where
+    V: ExpressionVisitor<T>,
+{
+    let mut scanner = Scanner::new(source);
+    let tokens = scanner
+        .scan_tokens()
+        .map_err(|error| error.to_lua_message(source))?;
+
+    let parser = Parser::new(tokens);
+    let expression = parser
+        .parse()
+        .map_err(|error| error.to_lua_message(source))?;
+
+    Ok(expression.visit(visitor))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::visitor::debug_visitor::DebugVisitor;
+
+    #[test]
+    fn should_parse_chunk() {
+        assert_eq!(
+            parse_chunk("foo.bar(1 + 2)", &DebugVisitor),
+            Ok(String::from("[foo.bar a:[+ l=`1` r=`2`], ]"))
+        );
+        assert_eq!(
+            parse_chunk("foo(1", &DebugVisitor),
+            Err(String::from(
+                "[string \"foo(1\"]:1: Expected ')' after arguments list near <eof>"
+            ))
+        );
+        assert_eq!(
+            parse_chunk("'foo", &DebugVisitor),
+            Err(String::from(
+                "[string \"'foo\"]:1: unfinished string near <eof>"
+            ))
+        );
+        assert_eq!(
+            parse_chunk("[[a]", &DebugVisitor),
+            Err(String::from(
+                "[string \"[[a]\"]:1: unfinished long string (starting at line 1) near <eof>"
+            ))
+        );
+        assert_eq!(
+            parse_chunk("'é' .. 1", &DebugVisitor),
+            Ok(String::from("[.. l=`'é'` r=`1`]"))
+        );
+        assert_eq!(
+            parse_chunk("0x10 + 1", &DebugVisitor),
+            Ok(String::from("[+ l=`0x10` r=`1`]"))
+        );
+        assert_eq!(
+            parse_chunk("1..2", &DebugVisitor),
+            Err(String::from(
+                "[string \"1..2\"]:1: malformed number near '1..2'"
+            ))
+        );
+        assert_eq!(
+            parse_chunk("foo.bar 'x' + 1", &DebugVisitor),
+            Ok(String::from("[+ l=[foo.bar a:`'x'`] r=`1`]"))
+        );
+    }
+
+    #[test]
+    fn should_fail_on_trailing_tokens() {
+        assert_eq!(
+            parse_chunk("1 2", &DebugVisitor),
+            Err(String::from(
+                "[string \"1 2\"]:1: '<eof>' expected near '2'"
+            ))
+        );
+        assert_eq!(
+            parse_chunk("foo() bar", &DebugVisitor),
+            Err(String::from(
+                "[string \"foo() bar\"]:1: '<eof>' expected near 'bar'"
+            ))
+        );
+    }
+
+    #[test]
+    fn should_fail_on_method_calls_without_arguments() {
+        assert_eq!(
+            parse_chunk("a:b", &DebugVisitor),
+            Err(String::from(
+                "[string \"a:b\"]:1: function arguments expected near <eof>"
+            ))
+        );
+        assert_eq!(
+            parse_chunk("a:b + 1", &DebugVisitor),
+            Err(String::from(
+                "[string \"a:b + 1\"]:1: function arguments expected near '+'"
+            ))
+        );
+    }
+}
@@ -1,11 +1,4 @@
-use parser::Parser;
-use visitor::debug_visitor;
-
-mod ast;
-mod parser;
-mod scanner;
-mod token;
-mod visitor;
+use lunatic::{transform::constant_folding, visitor::debug_visitor, Parser, Scanner};
 
 // const INPUT_SOURCE: &str = "
 // function fact (n)
@@ -28,20 +21,22 @@ mod visitor;
 // ";
 
 fn main() {
-    let mut scanner = scanner::Scanner::new("foo:bar '123'");
+    let source = "foo:bar '123'";
+    let mut scanner = Scanner::new(source);
     match scanner.scan_tokens() {
         Ok(tokens) => match Parser::new(tokens).parse() {
             Ok(expression) => {
+                let expression = constant_folding::fold_constants(expression);
                 let debug_visitor = debug_visitor::DebugVisitor;
                 let output = expression.visit(&debug_visitor);
                 println!("{}", output);
             }
             Err(error) => {
-                println!("Error parsing: {:?}", error);
+                println!("Error parsing: {}", error.to_lua_message(source));
             }
         },
         Err(error) => {
-            println!("Error scanning: {}", error);
+            println!("Error scanning: {}", error.to_lua_message(source));
         }
     }
 }
@@ -0,0 +1,326 @@
+//! Port of the parts of `lmathlib.c` of the reference implementation which don't need
+//! the runtime: integer and float rules of `math.floor`, `math.ceil`, `math.tointeger`,
+//! `math.fmod` and `math.ult`, and the xoshiro256** generator behind `math.random`.
+
+pub const MAX_INTEGER: i64 = i64::MAX;
+pub const MIN_INTEGER: i64 = i64::MIN;
+pub const HUGE: f64 = f64::INFINITY;
+pub const PI: f64 = std::f64::consts::PI;
+
+/// Number of bits of a float mantissa, which are taken from random integers (`FIGS`).
+const FLOAT_BITS: u32 = f64::MANTISSA_DIGITS;
+
+pub type MathResult<T> = Result<T, MathError>;
+
+#[derive(Debug, PartialEq)]
+pub struct MathError {
+    pub message: String,
+}
+
+impl MathError {
+    pub fn new<T>(message: &str) -> MathResult<T> {
+        Err(MathError {
+            message: String::from(message),
+        })
+    }
+}
+
+/// Number with the two subtypes of Lua 5.4, as reported by `math.type`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Number {
+    Integer(i64),
+    Float(f64),
+}
+
+impl Number {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Number::Integer(_) => "integer",
+            Number::Float(_) => "float",
+        }
+    }
+}
+
+/// Port of `lua_numbertointeger`: converts float with an integral value to an integer,
+/// if it's in range of integers.
+pub fn float_to_integer(value: f64) -> Option<i64> {
+    // `MIN_INTEGER` is exactly representable as a float, while `MAX_INTEGER` is not.
+    if value.fract() == 0.0 && value >= MIN_INTEGER as f64 && value < -(MIN_INTEGER as f64) {
+        Some(value as i64)
+    } else {
+        None
+    }
+}
+
+/// Port of `math.tointeger`, which fails for floats without an exact integer value.
+pub fn to_integer(value: Number) -> Option<i64> {
+    match value {
+        Number::Integer(value) => Some(value),
+        Number::Float(value) => float_to_integer(value),
+    }
+}
+
+/// Port of `pushnumint`: result of rounding is an integer when it fits into one.
+fn rounded(value: f64) -> Number {
+    match float_to_integer(value) {
+        Some(value) => Number::Integer(value),
+        None => Number::Float(value),
+    }
+}
+
+pub fn floor(value: Number) -> Number {
+    match value {
+        Number::Integer(_) => value,
+        Number::Float(value) => rounded(value.floor()),
+    }
+}
+
+pub fn ceil(value: Number) -> Number {
+    match value {
+        Number::Integer(_) => value,
+        Number::Float(value) => rounded(value.ceil()),
+    }
+}
+
+/// Port of `math.fmod`: remainder of integers truncates towards zero like in C, only
+/// floats use `fmod`.
+pub fn fmod(dividend: Number, divisor: Number) -> MathResult<Number> {
+    match (dividend, divisor) {
+        (Number::Integer(_), Number::Integer(0)) => {
+            MathError::new("bad argument #2 to 'math.fmod' (zero)")
+        }
+        // Avoids overflow of `MIN_INTEGER % -1`.
+        (Number::Integer(_), Number::Integer(-1)) => Ok(Number::Integer(0)),
+        (Number::Integer(dividend), Number::Integer(divisor)) => {
+            Ok(Number::Integer(dividend % divisor))
+        }
+        _ => Ok(Number::Float(to_float(dividend) % to_float(divisor))),
+    }
+}
+
+/// Port of `math.ult`: compares integers as unsigned.
+pub fn ult(left: i64, right: i64) -> bool {
+    (left as u64) < (right as u64)
+}
+
+fn to_float(value: Number) -> f64 {
+    match value {
+        Number::Integer(value) => value as f64,
+        Number::Float(value) => value,
+    }
+}
+
+/// Pseudo-random generator of `math.random`, xoshiro256** seeded the same way as
+/// `math.randomseed`, so that seeded sequences match the reference implementation.
+#[derive(Debug, Clone)]
+pub struct Random {
+    state: [u64; 4],
+}
+
+impl Random {
+    /// Port of `setseed`, same as `math.randomseed(first, second)`.
+    pub fn new(first: i64, second: i64) -> Random {
+        let mut random = Random {
+            // `0xff` avoids a zero state.
+            state: [first as u64, 0xff, second as u64, 0],
+        };
+
+        // Initial values are discarded to spread the seed.
+        for _ in 0..16 {
+            random.next_random();
+        }
+
+        random
+    }
+
+    /// Port of `nextrand`.
+    pub fn next_random(&mut self) -> u64 {
+        let [state0, state1, state2, state3] = self.state;
+        let state2 = state2 ^ state0;
+        let state3 = state3 ^ state1;
+
+        self.state = [
+            state0 ^ state3,
+            state1 ^ state2,
+            state2 ^ (state1 << 17),
+            state3.rotate_left(45),
+        ];
+
+        state1.wrapping_mul(5).rotate_left(7).wrapping_mul(9)
+    }
+
+    /// Same as `math.random()`: float in the interval `[0, 1)`.
+    pub fn float(&mut self) -> f64 {
+        // Port of `I2d`, which takes the higher bits of a random integer.
+        (self.next_random() >> (64 - FLOAT_BITS)) as f64 * 0.5f64.powi(FLOAT_BITS as i32)
+    }
+
+    /// Same as `math.random(0)`: integer with all bits random.
+    pub fn integer(&mut self) -> i64 {
+        self.next_random() as i64
+    }
+
+    /// Same as `math.random(low, high)`, `math.random(high)` is `range(1, high)`.
+    pub fn range(&mut self, low: i64, high: i64) -> MathResult<i64> {
+        // Value is drawn before checking the interval, same as in the reference.
+        let value = self.next_random();
+        if low > high {
+            return MathError::new("bad argument #1 to 'math.random' (interval is empty)");
+        }
+
+        let projected = self.project(value, (high as u64).wrapping_sub(low as u64));
+        Ok(projected.wrapping_add(low as u64) as i64)
+    }
+
+    /// Port of `project`: projects random integer into the interval `[0, size]` without
+    /// bias, by drawing again values which fall outside of it.
+    fn project(&mut self, value: u64, size: u64) -> u64 {
+        // `size + 1` is a power of 2.
+        if size & size.wrapping_add(1) == 0 {
+            return value & size;
+        }
+
+        // Smallest `2^b - 1` not smaller than `size`.
+        let limit = u64::MAX >> size.leading_zeros();
+        let mut value = value & limit;
+        while value > size {
+            value = self.next_random() & limit;
+        }
+
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Expected values come from Lua 5.4.
+
+    #[test]
+    fn should_round_to_integers_when_they_fit() {
+        assert_eq!(floor(Number::Float(3.7)), Number::Integer(3));
+        assert_eq!(floor(Number::Float(-3.5)), Number::Integer(-4));
+        assert_eq!(floor(Number::Integer(7)), Number::Integer(7));
+        assert_eq!(
+            floor(Number::Float(2f64.powi(63))),
+            Number::Float(2f64.powi(63))
+        );
+        assert_eq!(ceil(Number::Float(3.2)), Number::Integer(4));
+        assert_eq!(ceil(Number::Float(-0.5)), Number::Integer(0));
+        assert_eq!(
+            ceil(Number::Float(-(2f64.powi(63)))),
+            Number::Integer(MIN_INTEGER)
+        );
+        assert!(matches!(floor(Number::Float(f64::NAN)), Number::Float(value) if value.is_nan()));
+        assert_eq!(ceil(Number::Float(HUGE)), Number::Float(HUGE));
+    }
+
+    #[test]
+    fn should_convert_to_integers() {
+        assert_eq!(to_integer(Number::Float(3.0)), Some(3));
+        assert_eq!(to_integer(Number::Float(3.5)), None);
+        assert_eq!(to_integer(Number::Float(2f64.powi(63))), None);
+        assert_eq!(
+            to_integer(Number::Float(-(2f64.powi(63)))),
+            Some(MIN_INTEGER)
+        );
+        assert_eq!(to_integer(Number::Float(f64::NAN)), None);
+        assert_eq!(to_integer(Number::Integer(MAX_INTEGER)), Some(MAX_INTEGER));
+        assert_eq!(Number::Integer(1).type_name(), "integer");
+        assert_eq!(Number::Float(1.0).type_name(), "float");
+    }
+
+    #[test]
+    fn should_compute_remainders() {
+        let fmod_integers =
+            |dividend, divisor| fmod(Number::Integer(dividend), Number::Integer(divisor));
+
+        assert_eq!(fmod_integers(7, 3), Ok(Number::Integer(1)));
+        assert_eq!(fmod_integers(-7, 3), Ok(Number::Integer(-1)));
+        assert_eq!(fmod_integers(7, -3), Ok(Number::Integer(1)));
+        assert_eq!(fmod_integers(MIN_INTEGER, -1), Ok(Number::Integer(0)));
+        assert_eq!(
+            fmod_integers(1, 0).unwrap_err().message,
+            "bad argument #2 to 'math.fmod' (zero)"
+        );
+        assert_eq!(
+            fmod(Number::Float(7.5), Number::Integer(2)),
+            Ok(Number::Float(1.5))
+        );
+        assert_eq!(
+            fmod(Number::Integer(-7), Number::Float(3.0)),
+            Ok(Number::Float(-1.0))
+        );
+        assert_eq!(
+            fmod(Number::Integer(5), Number::Float(HUGE)),
+            Ok(Number::Float(5.0))
+        );
+        assert!(
+            matches!(fmod(Number::Integer(1), Number::Float(0.0)), Ok(Number::Float(value)) if value.is_nan())
+        );
+    }
+
+    #[test]
+    fn should_compare_as_unsigned() {
+        assert!(ult(1, 2));
+        assert!(!ult(-1, 2));
+        assert!(ult(2, -1));
+        assert!(ult(MAX_INTEGER, MIN_INTEGER));
+    }
+
+    #[test]
+    fn should_generate_seeded_sequences() {
+        let mut random = Random::new(42, 0);
+        assert_eq!(
+            [random.integer(), random.integer(), random.integer()],
+            [
+                -1276290044721465627,
+                8333941968102511665,
+                -8358531260401861301
+            ]
+        );
+
+        let mut random = Random::new(42, 0);
+        assert_eq!(
+            [random.float(), random.float()],
+            [0.9308121780395682, 0.4517838993592431]
+        );
+
+        let mut random = Random::new(42, 0);
+        let dice: Vec<_> = (0..10).map(|_| random.range(1, 6).unwrap()).collect();
+        assert_eq!(dice, vec![6, 2, 4, 6, 6, 2, 1, 3, 1, 1]);
+
+        let mut random = Random::new(-7, 123);
+        let values: Vec<_> = (0..5).map(|_| random.integer()).collect();
+        assert_eq!(
+            values,
+            vec![
+                3516377634032904156,
+                -7059163026588895008,
+                -4234383781704330710,
+                -4183078396787680942,
+                -8153166726234407163
+            ]
+        );
+    }
+
+    #[test]
+    fn should_generate_integers_in_range() {
+        let mut random = Random::new(0, 0);
+        let values: Vec<_> = (0..5).map(|_| random.range(1, 100).unwrap()).collect();
+        assert_eq!(values, vec![66, 9, 44, 22, 48]);
+
+        let mut random = Random::new(0, 0);
+        assert_eq!(
+            random.range(MIN_INTEGER, MAX_INTEGER),
+            Ok(-4668652479432084543)
+        );
+        assert_eq!(random.range(-10, -10), Ok(-10));
+        assert_eq!(random.range(3, 1000000007), Ok(760608686));
+        assert_eq!(
+            random.range(2, 1).unwrap_err().message,
+            "bad argument #1 to 'math.random' (interval is empty)"
+        );
+    }
+}
@@ -11,7 +11,7 @@ impl<'p> Parser<'p> {
         matches_token: M,
         parse_left: L,
         parse_right: R,
-    ) -> ParsingResult<Expression>
+    ) -> ParsingResult<'_, Expression<'_>>
     where
         M: FnOnce(&TokenType) -> bool,
         L: FnOnce() -> ParsingResult<'p, Expression<'p>>,
@@ -22,121 +22,132 @@ impl<'p> Parser<'p> {
         if let Some(token) = self.get_token() {
             if matches_token(&token.token_type) {
                 self.advance_cursor();
-                let right = parse_right()?;
-                return Ok(Expression::Binary {
-                    left: Box::new(left),
-                    operator: token,
-                    right: Box::new(right),
-                });
+                let right = self.parse_nested(parse_right)?;
+                return Ok(Expression::chain(left, vec![(token, right)]));
             }
         }
 
         Ok(left)
     }
+
+    /// Utility function to parse a chain of binary expressions of the same precedence,
+    /// eg: `1 - 2 + 3`. Operands are parsed in a loop into a single flat chain, so long
+    /// chains don't count towards the nesting limit.
+    fn try_parse_binary_chain<M, O>(
+        &self,
+        matches_token: M,
+        parse_operand: O,
+    ) -> ParsingResult<'_, Expression<'_>>
+    where
+        M: Fn(&TokenType) -> bool,
+        O: Fn() -> ParsingResult<'p, Expression<'p>>,
+    {
+        let first = parse_operand()?;
+        let mut rest = Vec::new();
+
+        while let Some(token) = self.get_token() {
+            if !matches_token(&token.token_type) {
+                break;
+            }
+
+            self.advance_cursor();
+            rest.push((token, parse_operand()?));
+        }
+
+        Ok(Expression::chain(first, rest))
+    }
 }
 
 /// Parsing methods.
 impl<'p> Parser<'p> {
-    pub(super) fn parse_maybe_binary_or(&self) -> ParsingResult<Expression> {
-        self.try_parse_binary_expression(
+    pub(super) fn parse_maybe_binary_or(&self) -> ParsingResult<'_, Expression<'_>> {
+        self.try_parse_binary_chain(
             |token_type| token_type == &TokenType::Or,
             || self.parse_maybe_binary_and(),
-            || self.parse_maybe_expression(),
         )
     }
 
-    pub(super) fn parse_maybe_binary_and(&self) -> ParsingResult<Expression> {
-        self.try_parse_binary_expression(
+    pub(super) fn parse_maybe_binary_and(&self) -> ParsingResult<'_, Expression<'_>> {
+        self.try_parse_binary_chain(
             |token_type| token_type == &TokenType::And,
             || self.parse_maybe_binary_relation(),
-            || self.parse_maybe_binary_and(),
         )
     }
 
-    pub(super) fn parse_maybe_binary_relation(&self) -> ParsingResult<Expression> {
-        self.try_parse_binary_expression(
-            |token_type| match token_type {
-                TokenType::Less
-                | TokenType::LessEqual
-                | TokenType::Greater
-                | TokenType::GreaterEqual
-                | TokenType::TildeEqual
-                | TokenType::EqualEqual => true,
-                _ => false,
+    pub(super) fn parse_maybe_binary_relation(&self) -> ParsingResult<'_, Expression<'_>> {
+        self.try_parse_binary_chain(
+            |token_type| {
+                matches!(
+                    token_type,
+                    TokenType::Less
+                        | TokenType::LessEqual
+                        | TokenType::Greater
+                        | TokenType::GreaterEqual
+                        | TokenType::TildeEqual
+                        | TokenType::EqualEqual
+                )
             },
             || self.parse_maybe_binary_bitwise_or(),
-            || self.parse_maybe_binary_relation(),
         )
     }
 
-    pub(super) fn parse_maybe_binary_bitwise_or(&self) -> ParsingResult<Expression> {
-        self.try_parse_binary_expression(
+    pub(super) fn parse_maybe_binary_bitwise_or(&self) -> ParsingResult<'_, Expression<'_>> {
+        self.try_parse_binary_chain(
             |token_type| token_type == &TokenType::Pipe,
             || self.parse_maybe_binary_bitwise_xor(),
-            || self.parse_maybe_binary_bitwise_or(),
         )
     }
 
-    pub(super) fn parse_maybe_binary_bitwise_xor(&self) -> ParsingResult<Expression> {
-        self.try_parse_binary_expression(
+    pub(super) fn parse_maybe_binary_bitwise_xor(&self) -> ParsingResult<'_, Expression<'_>> {
+        self.try_parse_binary_chain(
             |token_type| token_type == &TokenType::Tilde,
             || self.parse_maybe_binary_bitwise_and(),
-            || self.parse_maybe_binary_bitwise_xor(),
         )
     }
 
-    pub(super) fn parse_maybe_binary_bitwise_and(&self) -> ParsingResult<Expression> {
-        self.try_parse_binary_expression(
+    pub(super) fn parse_maybe_binary_bitwise_and(&self) -> ParsingResult<'_, Expression<'_>> {
+        self.try_parse_binary_chain(
             |token_type| token_type == &TokenType::Ampersand,
             || self.parse_maybe_binary_shift(),
-            || self.parse_maybe_binary_bitwise_and(),
         )
     }
 
-    pub(super) fn parse_maybe_binary_shift(&self) -> ParsingResult<Expression> {
-        self.try_parse_binary_expression(
-            |token_type| match token_type {
-                TokenType::LessLess | TokenType::GreaterGreater => true,
-                _ => false,
-            },
+    pub(super) fn parse_maybe_binary_shift(&self) -> ParsingResult<'_, Expression<'_>> {
+        self.try_parse_binary_chain(
+            |token_type| matches!(token_type, TokenType::LessLess | TokenType::GreaterGreater),
             || self.parse_maybe_binary_concat(),
-            || self.parse_maybe_binary_shift(),
         )
     }
 
-    pub(super) fn parse_maybe_binary_concat(&self) -> ParsingResult<Expression> {
-        self.try_parse_binary_expression(
+    pub(super) fn parse_maybe_binary_concat(&self) -> ParsingResult<'_, Expression<'_>> {
+        self.try_parse_binary_chain(
             |token_type| token_type == &TokenType::DotDot,
             || self.parse_maybe_binary_arithmetic_simple(),
-            || self.parse_maybe_binary_concat(),
         )
     }
 
-    pub(super) fn parse_maybe_binary_arithmetic_simple(&self) -> ParsingResult<Expression> {
-        self.try_parse_binary_expression(
-            |token_type| match token_type {
-                TokenType::Plus | TokenType::Minus => true,
-                _ => false,
-            },
+    pub(super) fn parse_maybe_binary_arithmetic_simple(&self) -> ParsingResult<'_, Expression<'_>> {
+        self.try_parse_binary_chain(
+            |token_type| matches!(token_type, TokenType::Plus | TokenType::Minus),
             || self.parse_maybe_binary_arithmetic_complex(),
-            || self.parse_maybe_binary_arithmetic_simple(),
         )
     }
 
-    pub(super) fn parse_maybe_binary_arithmetic_complex(&self) -> ParsingResult<Expression> {
-        self.try_parse_binary_expression(
-            |token_type| match token_type {
-                TokenType::Star | TokenType::Slash | TokenType::SlashSlash | TokenType::Percent => {
-                    true
-                }
-                _ => false,
+    pub(super) fn parse_maybe_binary_arithmetic_complex(
+        &self,
+    ) -> ParsingResult<'_, Expression<'_>> {
+        self.try_parse_binary_chain(
+            |token_type| {
+                matches!(
+                    token_type,
+                    TokenType::Star | TokenType::Slash | TokenType::SlashSlash | TokenType::Percent
+                )
             },
             || self.parse_maybe_unary(),
-            || self.parse_maybe_binary_arithmetic_complex(),
         )
     }
 
-    pub(super) fn parse_maybe_binary_exponent(&self) -> ParsingResult<Expression> {
+    pub(super) fn parse_maybe_binary_exponent(&self) -> ParsingResult<'_, Expression<'_>> {
         self.try_parse_binary_expression(
             |token_type| token_type == &TokenType::Caret,
             || self.parse_maybe_function_call(),
@@ -5,15 +5,15 @@ use crate::{
 };
 
 impl<'p> Parser<'p> {
-    pub(super) fn parse_maybe_spread(&self) -> ParsingResult<Expression> {
+    pub(super) fn parse_maybe_spread(&self) -> ParsingResult<'_, Expression<'_>> {
         self.parse_maybe_function_definition()
     }
 
-    fn parse_maybe_function_definition(&self) -> ParsingResult<Expression> {
+    fn parse_maybe_function_definition(&self) -> ParsingResult<'_, Expression<'_>> {
         self.parse_maybe_binary_or()
     }
 
-    pub(super) fn try_parse_identifier(&self) -> ParsingResult<Option<Identifier>> {
+    pub(super) fn try_parse_identifier(&self) -> ParsingResult<'_, Option<Identifier<'_>>> {
         if let Some(token) = self.get_token() {
             return match token.token_type {
                 TokenType::Identifier => {
@@ -27,7 +27,7 @@ impl<'p> Parser<'p> {
         ParsingError::end_of_tokens(self.get_last_token())
     }
 
-    pub(super) fn parse_maybe_literal(&self) -> ParsingResult<Expression> {
+    pub(super) fn parse_maybe_literal(&self) -> ParsingResult<'_, Expression<'_>> {
         if let Some(token) = self.get_token() {
             return match token.token_type {
                 TokenType::False
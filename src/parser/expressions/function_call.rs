@@ -5,48 +5,51 @@ use crate::{
 };
 
 impl<'p> Parser<'p> {
-    pub(super) fn parse_maybe_function_call(&self) -> ParsingResult<Expression> {
-        match self.parse_maybe_var_access()? {
-            Expression::Prefix(prefix) => {
-                if let Some(token) = self.get_token() {
-                    if token.token_type == TokenType::Colon {
-                        self.advance_cursor();
+    pub(super) fn parse_maybe_function_call(&self) -> ParsingResult<'_, Expression<'_>> {
+        let prefix = match self.try_parse_var_access()? {
+            Some(prefix) => prefix,
+            None => return self.parse_maybe_table_constructor(),
+        };
 
-                        if let Some(identifier) = self.try_parse_identifier()? {
-                            return match self.try_parse_function_args()? {
-                                Some(args) => Ok(Expression::Prefix(Prefix::FunctionCall(
-                                    FunctionCall::MethodCall {
-                                        callee: Box::new(prefix),
-                                        method: identifier,
-                                        args,
-                                    },
-                                ))),
-                                None => Ok(Expression::Prefix(prefix)),
-                            };
-                        }
+        if let Some(token) = self.get_token() {
+            if token.token_type == TokenType::Colon {
+                self.advance_cursor();
 
-                        return ParsingError::new(
-                            "Expected identifier after `:` in method call",
-                            self.get_last_token(),
-                        );
-                    }
+                if let Some(identifier) = self.try_parse_identifier()? {
+                    return match self.try_parse_function_args()? {
+                        Some(args) => Ok(Expression::Prefix(Prefix::FunctionCall(
+                            FunctionCall::MethodCall {
+                                callee: Box::new(prefix),
+                                method: identifier,
+                                args,
+                            },
+                        ))),
+                        None => ParsingError::new(
+                            "function arguments expected",
+                            self.get_token().unwrap_or(self.get_last_token()),
+                        ),
+                    };
                 }
 
-                match self.try_parse_function_args()? {
-                    Some(args) => Ok(Expression::Prefix(Prefix::FunctionCall(
-                        FunctionCall::FunctionCall {
-                            callee: Box::new(prefix),
-                            args,
-                        },
-                    ))),
-                    None => Ok(Expression::Prefix(prefix)),
-                }
+                return ParsingError::new(
+                    "Expected identifier after `:` in method call",
+                    self.get_last_token(),
+                );
             }
-            expression => Ok(expression),
+        }
+
+        match self.try_parse_function_args()? {
+            Some(args) => Ok(Expression::Prefix(Prefix::FunctionCall(
+                FunctionCall::FunctionCall {
+                    callee: Box::new(prefix),
+                    args,
+                },
+            ))),
+            None => Ok(Expression::Prefix(prefix)),
         }
     }
 
-    fn try_parse_function_args(&self) -> ParsingResult<Option<Args>> {
+    fn try_parse_function_args(&self) -> ParsingResult<'_, Option<Args<'_>>> {
         if let Some(token) = self.get_token() {
             return match token.token_type {
                 TokenType::LeftParen => {
@@ -80,7 +83,10 @@ impl<'p> Parser<'p> {
                     let table_constructor = self.parse_table_constructor()?;
                     Ok(Some(Args::TableConstructor(table_constructor)))
                 }
-                TokenType::LiteralString => Ok(Some(Args::LiteralString(token))),
+                TokenType::LiteralString => {
+                    self.advance_cursor();
+                    Ok(Some(Args::LiteralString(token)))
+                }
                 _ => Ok(None),
             };
         }
@@ -12,7 +12,7 @@ use crate::{
 };
 
 impl<'p> Parser<'p> {
-    pub(super) fn parse_maybe_expression(&self) -> ParsingResult<Expression> {
-        self.parse_maybe_spread()
+    pub(super) fn parse_maybe_expression(&self) -> ParsingResult<'_, Expression<'_>> {
+        self.parse_nested(|| self.parse_maybe_spread())
     }
 }
@@ -1,19 +1,20 @@
 use crate::{
-    ast::{Expression, Identifier, Prefix, Variable},
+    ast::{Identifier, Prefix, Variable},
     parser::{parsing_error::ParsingError, Parser, ParsingResult},
     token::TokenType,
 };
 
 impl<'p> Parser<'p> {
-    pub(super) fn parse_maybe_prefix(&self) -> ParsingResult<Expression> {
+    /// Parses prefix, returning `None` if current token cannot start one.
+    pub(super) fn try_parse_prefix(&self) -> ParsingResult<'_, Option<Prefix<'_>>> {
         if let Some(token) = self.get_token() {
             return match token.token_type {
                 TokenType::Identifier => {
                     self.advance_cursor();
 
-                    Ok(Expression::Prefix(Prefix::Variable(Variable::Identifier(
-                        Identifier(token),
-                    ))))
+                    Ok(Some(Prefix::Variable(Variable::Identifier(Identifier(
+                        token,
+                    )))))
                 }
                 // TODO: functioncall
                 TokenType::LeftParen => {
@@ -24,9 +25,9 @@ impl<'p> Parser<'p> {
                     self.assert_token(TokenType::RightParen, "Expected `)` after expression")?;
                     self.advance_cursor();
 
-                    Ok(Expression::Prefix(Prefix::Group(Box::new(expression))))
+                    Ok(Some(Prefix::Group(Box::new(expression))))
                 }
-                _ => self.parse_maybe_table_constructor(),
+                _ => Ok(None),
             };
         }
 
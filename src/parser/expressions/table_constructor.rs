@@ -1,11 +1,11 @@
 use crate::{
-    ast::{Expression, Field, Prefix, TableConstructor, Variable},
+    ast::{Expression, Field, Identifier, Prefix, TableConstructor, Variable},
     parser::{parsing_error::ParsingError, Parser, ParsingResult},
     token::TokenType,
 };
 
 impl<'p> Parser<'p> {
-    pub(super) fn parse_maybe_table_constructor(&self) -> ParsingResult<Expression> {
+    pub(super) fn parse_maybe_table_constructor(&self) -> ParsingResult<'_, Expression<'_>> {
         if let Some(token) = self.get_token() {
             return match token.token_type {
                 TokenType::LeftBrace => {
@@ -19,7 +19,7 @@ impl<'p> Parser<'p> {
         ParsingError::end_of_tokens(self.get_last_token())
     }
 
-    pub(super) fn parse_table_constructor(&self) -> ParsingResult<TableConstructor> {
+    pub(super) fn parse_table_constructor(&self) -> ParsingResult<'_, TableConstructor<'_>> {
         self.advance_cursor();
 
         let mut fields = Vec::new();
@@ -38,7 +38,7 @@ impl<'p> Parser<'p> {
         Ok(TableConstructor { fields })
     }
 
-    fn parse_field(&self) -> ParsingResult<Field> {
+    fn parse_field(&self) -> ParsingResult<'_, Field<'_>> {
         if let Some(token) = self.get_token() {
             if token.token_type == TokenType::LeftBracket {
                 self.advance_cursor();
@@ -61,11 +61,13 @@ impl<'p> Parser<'p> {
                 if self.is_token_of_type(&[TokenType::Equal]) {
                     self.advance_cursor();
 
-                    return match expression {
-                        Expression::Prefix(Prefix::Variable(Variable::Identifier(identifier))) => {
+                    return match &expression {
+                        Expression::Prefix(Prefix::Variable(Variable::Identifier(Identifier(
+                            token,
+                        )))) => {
                             let value = self.parse_maybe_expression()?;
                             Ok(Field::Normal {
-                                key: identifier,
+                                key: Identifier(token),
                                 value,
                             })
                         }
@@ -5,12 +5,12 @@ use crate::{
 };
 
 impl<'p> Parser<'p> {
-    pub(super) fn parse_maybe_unary(&self) -> ParsingResult<Expression> {
+    pub(super) fn parse_maybe_unary(&self) -> ParsingResult<'_, Expression<'_>> {
         if let Some(token) = self.get_token() {
             return match token.token_type {
                 TokenType::Minus | TokenType::Not | TokenType::Hash | TokenType::Tilde => {
                     self.advance_cursor();
-                    let right = self.parse_maybe_unary()?;
+                    let right = self.parse_nested(|| self.parse_maybe_unary())?;
                     Ok(Expression::Unary {
                         operator: token,
                         right: Box::new(right),
@@ -1,81 +1,75 @@
 use crate::{
-    ast::{Expression, Identifier, Prefix, Variable},
+    ast::{Prefix, Variable},
     parser::{parsing_error::ParsingError, Parser, ParsingResult},
     token::TokenType,
 };
 
 impl<'p> Parser<'p> {
-    pub(super) fn parse_maybe_var_access(&self) -> ParsingResult<Expression> {
-        match self.parse_maybe_var_identifier()? {
-            Expression::Prefix(prefix) => {
-                let mut current_prefix = prefix;
-                let mut error: ParsingResult<()> = Result::Ok(());
+    /// Parses prefix followed by member accesses, returning `None` if current token
+    /// cannot start a prefix.
+    pub(super) fn try_parse_var_access(&self) -> ParsingResult<'_, Option<Prefix<'_>>> {
+        let prefix = match self.try_parse_prefix()? {
+            Some(prefix) => prefix,
+            None => return Ok(None),
+        };
 
-                while let Some(token) = self.get_token() {
-                    match token.token_type {
-                        TokenType::LeftBracket => {
-                            self.advance_cursor();
+        // Each member access nests the previous prefix, so it takes a syntax level,
+        // same as a nested expression would.
+        let mut levels = 0;
+        let result = self.parse_member_accesses(prefix, &mut levels);
+        self.leave_levels(levels);
 
-                            let expression = self.parse_maybe_expression()?;
-                            current_prefix = Prefix::Variable(Variable::ExpressionMemberAccess {
-                                reference: Box::new(current_prefix),
-                                member: Box::new(expression),
-                            });
+        result.map(Some)
+    }
 
-                            self.assert_token(
-                                TokenType::RightBracket,
-                                "Expected `]` after expression",
-                            )?;
-                            self.advance_cursor();
-                        }
-                        TokenType::Dot => {
-                            self.advance_cursor();
+    fn parse_member_accesses<'s>(
+        &'s self,
+        prefix: Prefix<'s>,
+        levels: &mut usize,
+    ) -> ParsingResult<'s, Prefix<'s>> {
+        let mut current_prefix = prefix;
 
-                            match self.try_parse_identifier()? {
-                                Some(identifier) => {
-                                    current_prefix = Prefix::Variable(Variable::MemberAccess {
-                                        reference: Box::new(current_prefix),
-                                        member: identifier,
-                                    })
-                                }
-                                None => {
-                                    error = ParsingError::new(
-                                        "Expected identifier after `.`",
-                                        self.get_token().unwrap_or(self.get_last_token()),
-                                    );
-                                    break;
-                                }
-                            };
-                        }
-                        _ => {
-                            break;
-                        }
-                    }
-                }
+        while let Some(token) = self.get_token() {
+            match token.token_type {
+                TokenType::LeftBracket => {
+                    self.enter_level()?;
+                    *levels += 1;
+                    self.advance_cursor();
 
-                if error.is_err() {
-                    return Err(error.unwrap_err());
-                }
+                    // The level entered above covers the index expression too.
+                    let expression = self.parse_maybe_spread()?;
+                    current_prefix = Prefix::Variable(Variable::ExpressionMemberAccess {
+                        reference: Box::new(current_prefix),
+                        member: Box::new(expression),
+                    });
 
-                Ok(Expression::Prefix(current_prefix))
-            }
-            expression => Ok(expression),
-        }
-    }
-
-    fn parse_maybe_var_identifier(&self) -> ParsingResult<Expression> {
-        if let Some(token) = self.get_token() {
-            return match token.token_type {
-                TokenType::Identifier => {
+                    self.assert_token(TokenType::RightBracket, "Expected `]` after expression")?;
                     self.advance_cursor();
-                    return Ok(Expression::Prefix(Prefix::Variable(Variable::Identifier(
-                        Identifier(token),
-                    ))));
                 }
-                _ => self.parse_maybe_prefix(),
-            };
+                TokenType::Dot => {
+                    self.enter_level()?;
+                    *levels += 1;
+                    self.advance_cursor();
+
+                    match self.try_parse_identifier()? {
+                        Some(identifier) => {
+                            current_prefix = Prefix::Variable(Variable::MemberAccess {
+                                reference: Box::new(current_prefix),
+                                member: identifier,
+                            })
+                        }
+                        None => {
+                            return ParsingError::new(
+                                "Expected identifier after `.`",
+                                self.get_token().unwrap_or(self.get_last_token()),
+                            );
+                        }
+                    };
+                }
+                _ => break,
+            }
         }
 
-        ParsingError::end_of_tokens(self.get_last_token())
+        Ok(current_prefix)
     }
 }
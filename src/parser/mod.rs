@@ -1,14 +1,22 @@
 mod expressions;
+#[allow(clippy::module_inception)]
 mod parser;
 mod parser_utils;
 mod parsing_error;
 
 pub use parser::*;
+pub use parsing_error::ParsingError;
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{debug_visitor, scanner};
+    use crate::{
+        ast::Expression,
+        scanner,
+        token::{Token, TokenType},
+        visitor::debug_visitor,
+    };
+    use std::thread;
 
     fn expect_source_to_equal_ast(source: &str, expected: &str) {
         let mut scanner = scanner::Scanner::new(source);
@@ -20,6 +28,37 @@ mod tests {
         assert_eq!(&output[..], expected);
     }
 
+    fn nested_tokens<'a>(
+        prefix: &[(TokenType, &'a str)],
+        levels: usize,
+        suffix: &[(TokenType, &'a str)],
+    ) -> Vec<Token<'a>> {
+        let mut tokens = Vec::new();
+        for _ in 0..levels {
+            for (token_type, lexeme) in prefix {
+                tokens.push(Token::new(*token_type, lexeme, tokens.len(), None, 1));
+            }
+        }
+        tokens.push(Token::new(
+            TokenType::Numeral,
+            "1",
+            tokens.len(),
+            Some("1"),
+            1,
+        ));
+        for _ in 0..levels {
+            for (token_type, lexeme) in suffix {
+                tokens.push(Token::new(*token_type, lexeme, tokens.len(), None, 1));
+            }
+        }
+        tokens.push(Token::new(TokenType::Eof, "", tokens.len(), None, 1));
+        tokens
+    }
+
+    fn parse_error_message(tokens: &[Token]) -> String {
+        Parser::new(tokens).parse().unwrap_err().message
+    }
+
     #[test]
     fn should_parse_expressions() {
         expect_source_to_equal_ast(
@@ -96,6 +135,400 @@ mod tests {
         );
     }
 
+    #[test]
+    fn should_parse_operator_associativity() {
+        expect_source_to_equal_ast("1 - 2 - 3", "[- l=[- l=`1` r=`2`] r=`3`]");
+        expect_source_to_equal_ast("8 // 4 * 2 % 3", "[% l=[* l=[// l=`8` r=`4`] r=`2`] r=`3`]");
+        expect_source_to_equal_ast("1 < 2 == true", "[== l=[< l=`1` r=`2`] r=`true`]");
+        expect_source_to_equal_ast("1 << 2 >> 3", "[>> l=[<< l=`1` r=`2`] r=`3`]");
+        expect_source_to_equal_ast("1 | 2 | 3", "[| l=[| l=`1` r=`2`] r=`3`]");
+        expect_source_to_equal_ast("'a' .. 'b' .. 'c'", "[.. l=`'a'` r=[.. l=`'b'` r=`'c'`]]");
+        expect_source_to_equal_ast("2 ^ 3 ^ 2", "[^ l=`2` r=[^ l=`3` r=`2`]]");
+    }
+
+    #[test]
+    fn should_parse_nested_expressions_up_to_limit() {
+        let tokens = nested_tokens(
+            &[(TokenType::LeftParen, "(")],
+            60,
+            &[(TokenType::RightParen, ")")],
+        );
+        assert!(Parser::new(&tokens).parse().is_ok());
+
+        let tokens = nested_tokens(
+            &[(TokenType::LeftBrace, "{")],
+            60,
+            &[(TokenType::RightBrace, "}")],
+        );
+        assert!(Parser::new(&tokens).parse().is_ok());
+
+        let tokens = nested_tokens(&[(TokenType::Minus, "-")], 60, &[]);
+        assert!(Parser::new(&tokens).parse().is_ok());
+    }
+
+    #[test]
+    fn should_count_one_level_per_nesting() {
+        let message = "chunk has too many syntax levels";
+        let index = [
+            (TokenType::Identifier, "foo"),
+            (TokenType::LeftBracket, "["),
+        ];
+
+        // The whole expression takes the first level.
+        let tokens = nested_tokens(
+            &[(TokenType::LeftParen, "(")],
+            63,
+            &[(TokenType::RightParen, ")")],
+        );
+        assert!(Parser::new(&tokens).parse().is_ok());
+        let tokens = nested_tokens(&index, 63, &[(TokenType::RightBracket, "]")]);
+        assert!(Parser::new(&tokens).parse().is_ok());
+
+        let tokens = nested_tokens(
+            &[(TokenType::LeftParen, "(")],
+            64,
+            &[(TokenType::RightParen, ")")],
+        );
+        assert_eq!(parse_error_message(&tokens), message);
+        let tokens = nested_tokens(&index, 64, &[(TokenType::RightBracket, "]")]);
+        assert_eq!(parse_error_message(&tokens), message);
+    }
+
+    #[test]
+    fn should_fail_on_too_many_syntax_levels() {
+        let message = "chunk has too many syntax levels";
+
+        let tokens = nested_tokens(
+            &[(TokenType::LeftParen, "(")],
+            100_000,
+            &[(TokenType::RightParen, ")")],
+        );
+        assert_eq!(parse_error_message(&tokens), message);
+
+        let tokens = nested_tokens(
+            &[(TokenType::LeftBrace, "{")],
+            100_000,
+            &[(TokenType::RightBrace, "}")],
+        );
+        assert_eq!(parse_error_message(&tokens), message);
+
+        let tokens = nested_tokens(
+            &[
+                (TokenType::Identifier, "foo"),
+                (TokenType::LeftBracket, "["),
+            ],
+            100_000,
+            &[(TokenType::RightBracket, "]")],
+        );
+        assert_eq!(parse_error_message(&tokens), message);
+
+        let tokens = nested_tokens(&[(TokenType::Not, "not")], 100_000, &[]);
+        assert_eq!(parse_error_message(&tokens), message);
+
+        let tokens = nested_tokens(
+            &[(TokenType::Identifier, "foo"), (TokenType::Dot, ".")],
+            100_000,
+            &[],
+        );
+        assert_eq!(parse_error_message(&tokens), message);
+
+        let tokens = nested_tokens(
+            &[(TokenType::Numeral, "1"), (TokenType::Caret, "^")],
+            100_000,
+            &[],
+        );
+        assert_eq!(parse_error_message(&tokens), message);
+    }
+
+    #[test]
+    fn should_parse_long_operator_chains() {
+        for operator in [
+            (TokenType::Or, "or"),
+            (TokenType::And, "and"),
+            (TokenType::DotDot, ".."),
+        ] {
+            let tokens = nested_tokens(&[(TokenType::Numeral, "1"), operator], 99, &[]);
+            assert!(Parser::new(&tokens).parse().is_ok());
+        }
+
+        // Chains are flat, so a long one is a single node of the tree.
+        for operator in [
+            (TokenType::Or, "or"),
+            (TokenType::DotDot, ".."),
+            (TokenType::Plus, "+"),
+            (TokenType::EqualEqual, "=="),
+        ] {
+            let tokens = nested_tokens(&[(TokenType::Numeral, "1"), operator], 100_000, &[]);
+            match Parser::new(&tokens).parse() {
+                Ok(Expression::Binary { first, rest }) => {
+                    assert!(matches!(*first, Expression::Literal(_)));
+                    assert_eq!(rest.len(), 100_000);
+                }
+                result => panic!("expected binary chain, got {:?}", result),
+            }
+        }
+    }
+
+    #[test]
+    fn should_respect_configured_max_depth() {
+        let tokens = nested_tokens(
+            &[(TokenType::LeftParen, "(")],
+            9,
+            &[(TokenType::RightParen, ")")],
+        );
+        assert!(Parser::with_max_depth(&tokens, 10).parse().is_ok());
+
+        let tokens = nested_tokens(
+            &[(TokenType::LeftParen, "(")],
+            10,
+            &[(TokenType::RightParen, ")")],
+        );
+        assert_eq!(
+            Parser::with_max_depth(&tokens, 10)
+                .parse()
+                .unwrap_err()
+                .message,
+            "chunk has too many syntax levels"
+        );
+
+        // Deeper nesting is fine with a stack big enough for it.
+        let tokens = nested_tokens(
+            &[(TokenType::LeftBrace, "{")],
+            1_000,
+            &[(TokenType::RightBrace, "}")],
+        );
+        let is_ok = thread::Builder::new()
+            .stack_size(64 * 1024 * 1024)
+            .spawn(move || Parser::with_max_depth(&tokens, 2_000).parse().is_ok())
+            .unwrap()
+            .join()
+            .unwrap();
+        assert!(is_ok);
+    }
+
+    /// Xorshift generator, to keep fuzzing deterministic.
+    struct Random(u64);
+
+    impl Random {
+        fn below(&mut self, bound: usize) -> usize {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            (self.0 % bound as u64) as usize
+        }
+    }
+
+    const ALL_TOKENS: &[(TokenType, &str)] = &[
+        (TokenType::Dot, "."),
+        (TokenType::Comma, ","),
+        (TokenType::Colon, ":"),
+        (TokenType::Semicolon, ";"),
+        (TokenType::Equal, "="),
+        (TokenType::EqualEqual, "=="),
+        (TokenType::TildeEqual, "~="),
+        (TokenType::Less, "<"),
+        (TokenType::LessEqual, "<="),
+        (TokenType::LessLess, "<<"),
+        (TokenType::Greater, ">"),
+        (TokenType::GreaterEqual, ">="),
+        (TokenType::GreaterGreater, ">>"),
+        (TokenType::Plus, "+"),
+        (TokenType::Minus, "-"),
+        (TokenType::Star, "*"),
+        (TokenType::Slash, "/"),
+        (TokenType::SlashSlash, "//"),
+        (TokenType::Caret, "^"),
+        (TokenType::Percent, "%"),
+        (TokenType::Ampersand, "&"),
+        (TokenType::Tilde, "~"),
+        (TokenType::Pipe, "|"),
+        (TokenType::Hash, "#"),
+        (TokenType::LeftBracket, "["),
+        (TokenType::RightBracket, "]"),
+        (TokenType::LeftParen, "("),
+        (TokenType::RightParen, ")"),
+        (TokenType::LeftBrace, "{"),
+        (TokenType::RightBrace, "}"),
+        (TokenType::DotDot, ".."),
+        (TokenType::Spread, "..."),
+        (TokenType::Identifier, "foo"),
+        (TokenType::LiteralString, "'bar'"),
+        (TokenType::Numeral, "1"),
+        (TokenType::Or, "or"),
+        (TokenType::And, "and"),
+        (TokenType::End, "end"),
+        (TokenType::Function, "function"),
+        (TokenType::Nil, "nil"),
+        (TokenType::Not, "not"),
+        (TokenType::False, "false"),
+        (TokenType::True, "true"),
+        (TokenType::Return, "return"),
+        (TokenType::Local, "local"),
+        (TokenType::For, "for"),
+        (TokenType::Do, "do"),
+        (TokenType::In, "in"),
+        (TokenType::If, "if"),
+        (TokenType::Else, "else"),
+        (TokenType::Elseif, "elseif"),
+        (TokenType::Then, "then"),
+        (TokenType::Repeat, "repeat"),
+        (TokenType::Until, "until"),
+        (TokenType::While, "while"),
+        (TokenType::Goto, "goto"),
+        (TokenType::Break, "break"),
+    ];
+
+    /// Token sequences which open a new nesting level.
+    const NESTING_PREFIXES: &[&[(TokenType, &str)]] = &[
+        &[(TokenType::LeftParen, "(")],
+        &[(TokenType::LeftBrace, "{")],
+        &[(TokenType::LeftBrace, "{"), (TokenType::LeftBracket, "[")],
+        &[
+            (TokenType::LeftBrace, "{"),
+            (TokenType::Identifier, "foo"),
+            (TokenType::Equal, "="),
+        ],
+        &[
+            (TokenType::Identifier, "foo"),
+            (TokenType::LeftBracket, "["),
+        ],
+        &[(TokenType::Identifier, "foo"), (TokenType::LeftParen, "(")],
+        &[
+            (TokenType::Identifier, "foo"),
+            (TokenType::Colon, ":"),
+            (TokenType::Identifier, "bar"),
+            (TokenType::LeftParen, "("),
+        ],
+        &[(TokenType::Minus, "-")],
+        &[(TokenType::Not, "not")],
+        &[(TokenType::Numeral, "1"), (TokenType::Caret, "^")],
+    ];
+
+    /// Binary operators parsed in a loop into flat chains, which don't open a new
+    /// nesting level.
+    const CHAINING_OPERATORS: &[(TokenType, &str)] = &[
+        (TokenType::Or, "or"),
+        (TokenType::And, "and"),
+        (TokenType::EqualEqual, "=="),
+        (TokenType::Pipe, "|"),
+        (TokenType::LessLess, "<<"),
+        (TokenType::DotDot, ".."),
+        (TokenType::Plus, "+"),
+        (TokenType::Star, "*"),
+    ];
+
+    const CLOSING_TOKENS: &[(TokenType, &str)] = &[
+        (TokenType::RightParen, ")"),
+        (TokenType::RightBrace, "}"),
+        (TokenType::RightBracket, "]"),
+    ];
+
+    fn random_tokens<'a>(
+        random: &mut Random,
+        pool: &[(TokenType, &'a str)],
+        length: usize,
+    ) -> Vec<Token<'a>> {
+        let mut tokens: Vec<Token> = (0..length)
+            .map(|index| {
+                let (token_type, lexeme) = pool[random.below(pool.len())];
+                Token::new(token_type, lexeme, index, Some(lexeme), 1)
+            })
+            .collect();
+        tokens.push(Token::new(TokenType::Eof, "", length, None, 1));
+        tokens
+    }
+
+    #[test]
+    fn should_not_crash_on_random_tokens() {
+        let mut random = Random(0x2545_f491_4f6c_dd1d);
+
+        for _ in 0..20_000 {
+            let length = random.below(64);
+            let mut tokens = random_tokens(&mut random, ALL_TOKENS, length);
+            // Tokens built by hand may lack `Eof`, or be empty altogether.
+            if random.below(2) == 0 {
+                tokens.pop();
+            }
+            let _ = Parser::new(&tokens).parse();
+        }
+    }
+
+    #[test]
+    fn should_fail_on_missing_tokens() {
+        let parser = Parser::new(&[]);
+        let error = parser.parse().unwrap_err();
+        assert_eq!(
+            error.to_lua_message("=stdin"),
+            "stdin:1: unexpected symbol near <eof>"
+        );
+
+        let tokens = [Token::new(TokenType::Numeral, "1", 0, Some("1"), 1)];
+        assert_eq!(parse_error_message(&tokens), "'<eof>' expected");
+        let tokens = [Token::new(TokenType::LeftParen, "(", 0, None, 1)];
+        assert!(Parser::new(&tokens).parse().is_err());
+    }
+
+    #[test]
+    fn should_not_crash_on_random_deeply_nested_tokens() {
+        let mut random = Random(0x9e37_79b9_7f4a_7c15);
+
+        for _ in 0..200 {
+            let mut tokens = Vec::new();
+            for _ in 0..random.below(5_000) {
+                let prefix = NESTING_PREFIXES[random.below(NESTING_PREFIXES.len())];
+                for (token_type, lexeme) in prefix {
+                    tokens.push(Token::new(*token_type, lexeme, tokens.len(), None, 1));
+                }
+            }
+            tokens.push(Token::new(
+                TokenType::Numeral,
+                "1",
+                tokens.len(),
+                Some("1"),
+                1,
+            ));
+            let length = tokens.len();
+            tokens.append(&mut random_tokens(&mut random, CLOSING_TOKENS, length));
+
+            let _ = Parser::new(&tokens).parse();
+        }
+    }
+
+    #[test]
+    fn should_not_crash_on_long_operator_chains() {
+        let mut random = Random(0xd1b5_4a32_d192_ed03);
+
+        for _ in 0..8 {
+            let mut tokens = Vec::new();
+            for _ in 0..100_000 {
+                let (token_type, lexeme) =
+                    CHAINING_OPERATORS[random.below(CHAINING_OPERATORS.len())];
+                tokens.push(Token::new(
+                    TokenType::Numeral,
+                    "1",
+                    tokens.len(),
+                    Some("1"),
+                    1,
+                ));
+                tokens.push(Token::new(token_type, lexeme, tokens.len(), None, 1));
+            }
+            tokens.push(Token::new(
+                TokenType::Numeral,
+                "1",
+                tokens.len(),
+                Some("1"),
+                1,
+            ));
+            tokens.push(Token::new(TokenType::Eof, "", tokens.len(), None, 1));
+
+            let parser = Parser::new(&tokens);
+            let expression = parser.parse().unwrap();
+            assert!(expression
+                .visit(&debug_visitor::DebugVisitor)
+                .starts_with('['));
+        }
+    }
+
     #[test]
     fn should_parse_table_constructor() {
         expect_source_to_equal_ast("{ foo = 1, }", "Tc[`foo`=`1` ]");
@@ -158,5 +591,7 @@ mod tests {
             "foo.bar(true or false)",
             "[foo.bar a:[or l=`true` r=`false`], ]",
         );
+        expect_source_to_equal_ast("(\"abc\"):upper()", "[(`\"abc\"`):upper a:]");
+        expect_source_to_equal_ast("('abc'):rep(2)", "[(`'abc'`):rep a:`2`, ]");
     }
 }
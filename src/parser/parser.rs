@@ -1,9 +1,26 @@
 use super::parsing_error::ParsingError;
-use crate::{ast::Expression, token::Token};
+use crate::{
+    ast::Expression,
+    token::{Token, TokenType},
+};
 use std::cell::RefCell;
 
 pub type ParsingResult<'a, T> = Result<T, ParsingError<'a>>;
 
+/// Default maximum nesting of expressions. Lower than `LUAI_MAXCCALLS` (200) in the reference
+/// implementation, because each level takes a few KB of stack here (close to 20KB in debug
+/// builds) and parsing should fit into the default 2MB stack of a spawned thread.
+pub const DEFAULT_MAX_DEPTH: usize = 64;
+
+/// Token reported by errors when there are no tokens at all, not even `Eof`.
+pub(super) static MISSING_EOF: Token = Token {
+    token_type: TokenType::Eof,
+    lexeme: "",
+    lexeme_start: 0,
+    literal: None,
+    line: 1,
+};
+
 /// Lua parser.
 ///
 /// Specs:
@@ -12,18 +29,37 @@ pub type ParsingResult<'a, T> = Result<T, ParsingError<'a>>;
 pub struct Parser<'p> {
     pub(super) tokens: &'p [Token<'p>],
     pub(super) current: RefCell<usize>,
+    pub(super) depth: RefCell<usize>,
+    pub(super) max_depth: usize,
 }
 
 /// Public methods.
 impl<'p> Parser<'p> {
     pub fn new(tokens: &'p [Token<'p>]) -> Parser<'p> {
+        Parser::with_max_depth(tokens, DEFAULT_MAX_DEPTH)
+    }
+
+    /// Creates parser which fails with "chunk has too many syntax levels" error when
+    /// expressions are nested deeper than `max_depth`. Raise it only when parsing on
+    /// a thread with a stack big enough for the extra levels.
+    pub fn with_max_depth(tokens: &'p [Token<'p>], max_depth: usize) -> Parser<'p> {
         Parser {
             tokens,
             current: RefCell::new(0),
+            depth: RefCell::new(0),
+            max_depth,
         }
     }
 
-    pub fn parse(&self) -> ParsingResult<Expression> {
-        self.parse_maybe_expression()
+    /// Parses the whole chunk, failing if any tokens are left after the expression.
+    pub fn parse(&self) -> ParsingResult<'_, Expression<'_>> {
+        if self.tokens.is_empty() {
+            return ParsingError::new("unexpected symbol", &MISSING_EOF);
+        }
+
+        let expression = self.parse_maybe_expression()?;
+        self.assert_token(TokenType::Eof, "'<eof>' expected")?;
+
+        Ok(expression)
     }
 }
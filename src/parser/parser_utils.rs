@@ -1,4 +1,4 @@
-use super::{parsing_error::ParsingError, Parser, ParsingResult};
+use super::{parser::MISSING_EOF, parsing_error::ParsingError, Parser, ParsingResult};
 use crate::token::{Token, TokenType};
 
 impl<'p> Parser<'p> {
@@ -6,7 +6,7 @@ impl<'p> Parser<'p> {
         *self.current.borrow_mut() += 1;
     }
 
-    pub(super) fn get_token(&self) -> Option<&Token> {
+    pub(super) fn get_token(&self) -> Option<&Token<'_>> {
         if *self.current.borrow() >= self.tokens.len() {
             return None;
         }
@@ -14,8 +14,8 @@ impl<'p> Parser<'p> {
         Some(&self.tokens[*self.current.borrow()])
     }
 
-    pub(super) fn get_last_token(&self) -> &Token {
-        &self.tokens.last().unwrap()
+    pub(super) fn get_last_token(&self) -> &Token<'_> {
+        self.tokens.last().unwrap_or(&MISSING_EOF)
     }
 
     pub(super) fn is_token_of_type(&self, token_types: &[TokenType]) -> bool {
@@ -27,7 +27,7 @@ impl<'p> Parser<'p> {
                     }
                 }
 
-                return false;
+                false
             }
             None => false,
         }
@@ -44,4 +44,35 @@ impl<'p> Parser<'p> {
 
         Ok(())
     }
+
+    /// Runs nested parsing, failing with an error instead of overflowing the stack
+    /// on pathological input, eg: thousands of nested parentheses.
+    pub(super) fn parse_nested<T, F>(&self, parse: F) -> ParsingResult<'_, T>
+    where
+        F: FnOnce() -> ParsingResult<'p, T>,
+    {
+        self.enter_level()?;
+        let result = parse();
+        self.leave_levels(1);
+
+        result
+    }
+
+    /// Accounts for a nesting level which isn't parsed recursively, but still nests
+    /// in the tree, eg: each suffix of `a.b.c`.
+    pub(super) fn enter_level(&self) -> ParsingResult<'_, ()> {
+        if *self.depth.borrow() >= self.max_depth {
+            return ParsingError::new(
+                "chunk has too many syntax levels",
+                self.get_token().unwrap_or(self.get_last_token()),
+            );
+        }
+
+        *self.depth.borrow_mut() += 1;
+        Ok(())
+    }
+
+    pub(super) fn leave_levels(&self, levels: usize) {
+        *self.depth.borrow_mut() -= levels;
+    }
 }
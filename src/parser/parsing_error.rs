@@ -1,5 +1,8 @@
+use crate::{
+    chunk_id::chunk_id,
+    token::{Token, TokenType},
+};
 use std::convert::From;
-use crate::token::Token;
 
 use super::ParsingResult;
 
@@ -9,9 +12,12 @@ pub struct ParsingError<'a> {
     pub token: &'a Token<'a>,
 }
 
-impl <'a> ParsingError<'a> {
+impl<'a> ParsingError<'a> {
     pub fn new<T>(message: &'a str, token: &'a Token<'a>) -> ParsingResult<'a, T> {
-        Err(ParsingError { message: String::from(message), token })
+        Err(ParsingError {
+            message: String::from(message),
+            token,
+        })
     }
 
     pub fn end_of_tokens<T>(token: &'a Token<'a>) -> ParsingResult<'a, T> {
@@ -22,3 +28,53 @@ impl <'a> ParsingError<'a> {
         ParsingError::new("Unexpected token", token)
     }
 }
+
+/// Formatting of errors as reported by the reference implementation.
+impl<'a> ParsingError<'a> {
+    /// Formats the error as `chunkname:line: message near 'token'`, where `chunk_name`
+    /// follows the `load` conventions: `=name` and `@file` are used as is, anything else
+    /// is treated as the chunk source and shown as `[string "..."]`.
+    pub fn to_lua_message(&self, chunk_name: &str) -> String {
+        let near = match self.token.token_type {
+            TokenType::Eof => String::from("<eof>"),
+            _ => format!("'{}'", self.token.lexeme),
+        };
+
+        format!(
+            "{}:{}: {} near {}",
+            chunk_id(chunk_name),
+            self.token.line,
+            self.message,
+            near
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parser::Parser, scanner::Scanner};
+
+    fn parse_error_message(source: &str, chunk_name: &str) -> String {
+        let mut scanner = Scanner::new(source);
+        let tokens = scanner.scan_tokens().unwrap();
+        let parser = Parser::new(tokens);
+        let error = parser.parse().unwrap_err();
+        error.to_lua_message(chunk_name)
+    }
+
+    #[test]
+    fn should_format_error_like_reference_implementation() {
+        assert_eq!(
+            parse_error_message("foo(1", "foo(1"),
+            "[string \"foo(1\"]:1: Expected ')' after arguments list near <eof>"
+        );
+        assert_eq!(
+            parse_error_message("foo(1 2)", "=stdin"),
+            "stdin:1: Expected ')' after arguments list near '2'"
+        );
+        assert_eq!(
+            parse_error_message("\n\nfoo[1", "@script.lua"),
+            "script.lua:3: Expected `]` after expression near <eof>"
+        );
+    }
+}
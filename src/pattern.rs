@@ -0,0 +1,915 @@
+//! Port of pattern matching from `lstrlib.c` of the reference implementation, which
+//! backs `string.find`, `string.match`, `string.gmatch` and `string.gsub`.
+//!
+//! Subjects and patterns are byte strings, classes like `%a` follow the C locale.
+//! Replacing with a function or a table in `gsub` needs the runtime, so [`gsub`] takes
+//! a callback, which receives each match instead.
+
+/// Maximum number of captures in a pattern (`LUA_MAXCAPTURES`).
+pub const MAX_CAPTURES: usize = 32;
+
+/// Maximum recursion depth of matching (`MAXCCALLS`), deeper patterns fail with
+/// "pattern too complex" instead of overflowing the stack.
+const MAX_MATCH_DEPTH: usize = 200;
+
+const ESCAPE: u8 = b'%';
+
+pub type PatternResult<T> = Result<T, PatternError>;
+
+#[derive(Debug, PartialEq)]
+pub struct PatternError {
+    pub message: String,
+}
+
+impl PatternError {
+    pub fn new<T>(message: &str) -> PatternResult<T> {
+        Err(PatternError {
+            message: String::from(message),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CaptureLength {
+    Unfinished,
+    Position,
+    Length(usize),
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Capture {
+    start: usize,
+    length: CaptureLength,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum CaptureValue<'s> {
+    String(&'s [u8]),
+    /// Position capture `()`, 1-based like string indices in Lua.
+    Position(usize),
+}
+
+/// Match of a pattern, with byte offsets of its start and end (exclusive).
+#[derive(Debug)]
+pub struct Match<'s> {
+    subject: &'s [u8],
+    pub start: usize,
+    pub end: usize,
+    captures: Vec<Capture>,
+}
+
+impl<'s> Match<'s> {
+    /// Captures of the pattern, as returned by `string.find` after the indices.
+    pub fn captures(&self) -> PatternResult<Vec<CaptureValue<'s>>> {
+        (0..self.captures.len())
+            .map(|index| self.capture(index))
+            .collect()
+    }
+
+    /// Captures as returned by `string.match` and `string.gmatch`, or passed to `gsub`
+    /// replacement functions: the whole match if the pattern has no captures.
+    pub fn values(&self) -> PatternResult<Vec<CaptureValue<'s>>> {
+        if self.captures.is_empty() {
+            return Ok(vec![CaptureValue::String(
+                &self.subject[self.start..self.end],
+            )]);
+        }
+
+        self.captures()
+    }
+
+    /// Port of `get_onecapture`: capture at `index`, where index 0 of a pattern without
+    /// captures is the whole match.
+    pub fn capture(&self, index: usize) -> PatternResult<CaptureValue<'s>> {
+        match self.captures.get(index) {
+            None if index == 0 => Ok(CaptureValue::String(&self.subject[self.start..self.end])),
+            None => PatternError::new(&format!("invalid capture index %{}", index + 1)),
+            Some(capture) => match capture.length {
+                CaptureLength::Unfinished => PatternError::new("unfinished capture"),
+                CaptureLength::Position => Ok(CaptureValue::Position(capture.start + 1)),
+                CaptureLength::Length(length) => Ok(CaptureValue::String(
+                    &self.subject[capture.start..capture.start + length],
+                )),
+            },
+        }
+    }
+
+    /// Port of `add_s`: expands replacement string of `gsub`, where `%0` is the whole
+    /// match, `%1` to `%9` are captures and `%%` is a single `%`.
+    pub fn expand(&self, replacement: &[u8]) -> PatternResult<Vec<u8>> {
+        let mut output = Vec::with_capacity(replacement.len());
+        let mut bytes = replacement.iter();
+
+        while let Some(&byte) = bytes.next() {
+            if byte != ESCAPE {
+                output.push(byte);
+                continue;
+            }
+
+            match bytes.next() {
+                Some(&ESCAPE) => output.push(ESCAPE),
+                Some(&b'0') => output.extend_from_slice(&self.subject[self.start..self.end]),
+                Some(&digit) if digit.is_ascii_digit() => {
+                    match self.capture((digit - b'1') as usize)? {
+                        CaptureValue::String(value) => output.extend_from_slice(value),
+                        CaptureValue::Position(position) => {
+                            output.extend_from_slice(position.to_string().as_bytes())
+                        }
+                    }
+                }
+                _ => return PatternError::new("invalid use of '%' in replacement string"),
+            }
+        }
+
+        Ok(output)
+    }
+}
+
+/// Port of `str_find_aux`: finds the first match of `pattern` in `subject`, starting at
+/// byte offset `init`. A leading `^` anchors the match at `init`.
+pub fn find<'s>(
+    subject: &'s [u8],
+    pattern: &[u8],
+    init: usize,
+) -> PatternResult<Option<Match<'s>>> {
+    if init > subject.len() {
+        return Ok(None);
+    }
+
+    let (pattern, anchored) = strip_anchor(pattern);
+    let mut state = MatchState::new(subject, pattern);
+
+    for start in init..=subject.len() {
+        if let Some(found) = state.match_at(start)? {
+            return Ok(Some(found));
+        }
+
+        if anchored {
+            break;
+        }
+    }
+
+    Ok(None)
+}
+
+/// Port of `gmatch`: iterates over matches of `pattern` in `subject`, starting at byte
+/// offset `init`. A leading `^` is not an anchor here, as it would stop the iteration.
+pub fn gmatch<'s, 'p>(subject: &'s [u8], pattern: &'p [u8], init: usize) -> GMatch<'s, 'p> {
+    GMatch {
+        state: MatchState::new(subject, pattern),
+        position: init,
+        last_match: None,
+    }
+}
+
+pub struct GMatch<'s, 'p> {
+    state: MatchState<'s, 'p>,
+    position: usize,
+    last_match: Option<usize>,
+}
+
+impl<'s, 'p> Iterator for GMatch<'s, 'p> {
+    type Item = PatternResult<Match<'s>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.position <= self.state.subject.len() {
+            let start = self.position;
+            self.position += 1;
+
+            match self.state.match_at(start) {
+                // Empty match right after the previous one is skipped.
+                Ok(Some(found)) if Some(found.end) != self.last_match => {
+                    self.position = found.end;
+                    self.last_match = Some(found.end);
+                    return Some(Ok(found));
+                }
+                Ok(_) => {}
+                Err(error) => {
+                    self.position = usize::MAX;
+                    return Some(Err(error));
+                }
+            }
+        }
+
+        None
+    }
+}
+
+/// Port of `str_gsub`: replaces at most `max_replacements` matches of `pattern` in
+/// `subject` with what `replace` returns for them, keeping matches for which it returns
+/// `None`, like for `nil` or `false` from a function or table. Returns the result and
+/// the number of matches.
+///
+/// Replacing with a string is `|found| found.expand(replacement).map(Some)`.
+pub fn gsub<F, E>(
+    subject: &[u8],
+    pattern: &[u8],
+    max_replacements: Option<usize>,
+    mut replace: F,
+) -> Result<(Vec<u8>, usize), E>
+where
+    F: FnMut(&Match) -> Result<Option<Vec<u8>>, E>,
+    E: From<PatternError>,
+{
+    let (pattern, anchored) = strip_anchor(pattern);
+    let mut state = MatchState::new(subject, pattern);
+    let max_replacements = max_replacements.unwrap_or(usize::MAX);
+
+    let mut output = Vec::with_capacity(subject.len());
+    let mut position = 0;
+    let mut last_match = None;
+    let mut count = 0;
+
+    while count < max_replacements {
+        match state.match_at(position)? {
+            // Empty match right after the previous one is skipped.
+            Some(found) if Some(found.end) != last_match => {
+                count += 1;
+                match replace(&found)? {
+                    Some(replacement) => output.extend_from_slice(&replacement),
+                    None => output.extend_from_slice(&subject[found.start..found.end]),
+                }
+
+                position = found.end;
+                last_match = Some(found.end);
+            }
+            _ if position < subject.len() => {
+                output.push(subject[position]);
+                position += 1;
+            }
+            _ => break,
+        }
+
+        if anchored {
+            break;
+        }
+    }
+
+    output.extend_from_slice(&subject[position..]);
+    Ok((output, count))
+}
+
+fn strip_anchor(pattern: &[u8]) -> (&[u8], bool) {
+    match pattern.strip_prefix(b"^") {
+        Some(pattern) => (pattern, true),
+        None => (pattern, false),
+    }
+}
+
+/// Port of `match_class`, with classes of the C locale.
+fn match_class(char: u8, class: u8) -> bool {
+    let matches = match class.to_ascii_lowercase() {
+        b'a' => char.is_ascii_alphabetic(),
+        b'c' => char.is_ascii_control(),
+        b'd' => char.is_ascii_digit(),
+        b'g' => char.is_ascii_graphic(),
+        b'l' => char.is_ascii_lowercase(),
+        b'p' => char.is_ascii_punctuation(),
+        // Unlike `isspace`, `is_ascii_whitespace` doesn't include vertical tab.
+        b's' => char.is_ascii_whitespace() || char == 0x0b,
+        b'u' => char.is_ascii_uppercase(),
+        b'w' => char.is_ascii_alphanumeric(),
+        b'x' => char.is_ascii_hexdigit(),
+        _ => return class == char,
+    };
+
+    if class.is_ascii_uppercase() {
+        !matches
+    } else {
+        matches
+    }
+}
+
+struct MatchState<'s, 'p> {
+    subject: &'s [u8],
+    pattern: &'p [u8],
+    remaining_depth: usize,
+    captures: Vec<Capture>,
+}
+
+impl<'s, 'p> MatchState<'s, 'p> {
+    fn new(subject: &'s [u8], pattern: &'p [u8]) -> MatchState<'s, 'p> {
+        MatchState {
+            subject,
+            pattern,
+            remaining_depth: MAX_MATCH_DEPTH,
+            captures: Vec::new(),
+        }
+    }
+
+    fn match_at(&mut self, start: usize) -> PatternResult<Option<Match<'s>>> {
+        self.remaining_depth = MAX_MATCH_DEPTH;
+        self.captures.clear();
+
+        Ok(self.match_pattern(start, 0)?.map(|end| Match {
+            subject: self.subject,
+            start,
+            end,
+            captures: std::mem::take(&mut self.captures),
+        }))
+    }
+
+    /// Port of `match`: matches pattern from `p` against subject from `s`, returning
+    /// the end of the match.
+    fn match_pattern(&mut self, s: usize, p: usize) -> PatternResult<Option<usize>> {
+        if self.remaining_depth == 0 {
+            return PatternError::new("pattern too complex");
+        }
+
+        self.remaining_depth -= 1;
+        let result = self.match_pattern_in_loop(s, p);
+        self.remaining_depth += 1;
+
+        result
+    }
+
+    /// Tail calls of `match` are iterations of the loop, same as `goto init` in the
+    /// reference implementation.
+    fn match_pattern_in_loop(
+        &mut self,
+        mut s: usize,
+        mut p: usize,
+    ) -> PatternResult<Option<usize>> {
+        let pattern = self.pattern;
+
+        loop {
+            if p == pattern.len() {
+                return Ok(Some(s));
+            }
+
+            match pattern[p] {
+                b'(' => {
+                    return if pattern.get(p + 1) == Some(&b')') {
+                        self.start_capture(s, p + 2, CaptureLength::Position)
+                    } else {
+                        self.start_capture(s, p + 1, CaptureLength::Unfinished)
+                    };
+                }
+                b')' => return self.end_capture(s, p + 1),
+                b'$' if p + 1 == pattern.len() => {
+                    return Ok((s == self.subject.len()).then_some(s));
+                }
+                ESCAPE if pattern.get(p + 1) == Some(&b'b') => {
+                    match self.match_balance(s, p + 2)? {
+                        Some(end) => {
+                            s = end;
+                            p += 4;
+                        }
+                        None => return Ok(None),
+                    }
+                }
+                ESCAPE if pattern.get(p + 1) == Some(&b'f') => {
+                    p += 2;
+                    if pattern.get(p) != Some(&b'[') {
+                        return PatternError::new("missing '[' after '%f' in pattern");
+                    }
+
+                    let class_end = self.class_end(p)?;
+                    let previous = if s == 0 { 0 } else { self.subject[s - 1] };
+                    let current = self.subject.get(s).copied().unwrap_or(0);
+
+                    if self.match_bracket_class(previous, p, class_end - 1)
+                        || !self.match_bracket_class(current, p, class_end - 1)
+                    {
+                        return Ok(None);
+                    }
+
+                    p = class_end;
+                }
+                ESCAPE if pattern.get(p + 1).is_some_and(u8::is_ascii_digit) => {
+                    match self.match_capture(s, pattern[p + 1])? {
+                        Some(end) => {
+                            s = end;
+                            p += 2;
+                        }
+                        None => return Ok(None),
+                    }
+                }
+                _ => {
+                    let class_end = self.class_end(p)?;
+                    let suffix = pattern.get(class_end).copied();
+
+                    if !self.single_match(s, p, class_end) {
+                        // Quantifiers which accept zero repetitions.
+                        if matches!(suffix, Some(b'*' | b'?' | b'-')) {
+                            p = class_end + 1;
+                            continue;
+                        }
+
+                        return Ok(None);
+                    }
+
+                    match suffix {
+                        Some(b'?') => {
+                            if let Some(end) = self.match_pattern(s + 1, class_end + 1)? {
+                                return Ok(Some(end));
+                            }
+
+                            p = class_end + 1;
+                        }
+                        Some(b'+') => return self.max_expand(s + 1, p, class_end),
+                        Some(b'*') => return self.max_expand(s, p, class_end),
+                        Some(b'-') => return self.min_expand(s, p, class_end),
+                        _ => {
+                            s += 1;
+                            p = class_end;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Port of `classEnd`: returns index past the single character class at `p`.
+    fn class_end(&self, p: usize) -> PatternResult<usize> {
+        let pattern = self.pattern;
+        let mut p = p + 1;
+
+        match pattern[p - 1] {
+            ESCAPE => {
+                if p >= pattern.len() {
+                    return PatternError::new("malformed pattern (ends with '%')");
+                }
+
+                Ok(p + 1)
+            }
+            b'[' => {
+                if pattern.get(p) == Some(&b'^') {
+                    p += 1;
+                }
+
+                // First character of the set is never its end, eg: `[]]` is a set of `]`.
+                loop {
+                    if p >= pattern.len() {
+                        return PatternError::new("malformed pattern (missing ']')");
+                    }
+
+                    p += 1;
+                    if pattern[p - 1] == ESCAPE && p < pattern.len() {
+                        p += 1;
+                    }
+
+                    if pattern.get(p) == Some(&b']') {
+                        return Ok(p + 1);
+                    }
+                }
+            }
+            _ => Ok(p),
+        }
+    }
+
+    /// Port of `matchbracketclass`: matches set starting at `p` and ending at `end`,
+    /// which is the index of its closing `]`.
+    fn match_bracket_class(&self, char: u8, p: usize, end: usize) -> bool {
+        let pattern = self.pattern;
+        let mut p = p + 1;
+
+        let negated = pattern[p] == b'^';
+        if negated {
+            p += 1;
+        }
+
+        while p < end {
+            if pattern[p] == ESCAPE {
+                if match_class(char, pattern[p + 1]) {
+                    return !negated;
+                }
+
+                p += 2;
+            } else if pattern[p + 1] == b'-' && p + 2 < end {
+                if pattern[p] <= char && char <= pattern[p + 2] {
+                    return !negated;
+                }
+
+                p += 3;
+            } else {
+                if pattern[p] == char {
+                    return !negated;
+                }
+
+                p += 1;
+            }
+        }
+
+        negated
+    }
+
+    fn single_match(&self, s: usize, p: usize, class_end: usize) -> bool {
+        match self.subject.get(s) {
+            Some(&char) => match self.pattern[p] {
+                b'.' => true,
+                ESCAPE => match_class(char, self.pattern[p + 1]),
+                b'[' => self.match_bracket_class(char, p, class_end - 1),
+                pattern_char => pattern_char == char,
+            },
+            None => false,
+        }
+    }
+
+    fn match_balance(&self, s: usize, p: usize) -> PatternResult<Option<usize>> {
+        if p + 1 >= self.pattern.len() {
+            return PatternError::new("malformed pattern (missing arguments to '%b')");
+        }
+
+        let (open, close) = (self.pattern[p], self.pattern[p + 1]);
+        if self.subject.get(s) != Some(&open) {
+            return Ok(None);
+        }
+
+        let mut depth = 1;
+        for (index, &char) in self.subject.iter().enumerate().skip(s + 1) {
+            if char == close {
+                depth -= 1;
+                if depth == 0 {
+                    return Ok(Some(index + 1));
+                }
+            } else if char == open {
+                depth += 1;
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn max_expand(&mut self, s: usize, p: usize, class_end: usize) -> PatternResult<Option<usize>> {
+        let mut count = 0;
+        while self.single_match(s + count, p, class_end) {
+            count += 1;
+        }
+
+        // Longest repetition is tried first, backing off one at a time.
+        loop {
+            if let Some(end) = self.match_pattern(s + count, class_end + 1)? {
+                return Ok(Some(end));
+            }
+
+            if count == 0 {
+                return Ok(None);
+            }
+
+            count -= 1;
+        }
+    }
+
+    fn min_expand(&mut self, s: usize, p: usize, class_end: usize) -> PatternResult<Option<usize>> {
+        let mut s = s;
+
+        loop {
+            if let Some(end) = self.match_pattern(s, class_end + 1)? {
+                return Ok(Some(end));
+            }
+
+            if !self.single_match(s, p, class_end) {
+                return Ok(None);
+            }
+
+            s += 1;
+        }
+    }
+
+    fn start_capture(
+        &mut self,
+        s: usize,
+        p: usize,
+        length: CaptureLength,
+    ) -> PatternResult<Option<usize>> {
+        if self.captures.len() >= MAX_CAPTURES {
+            return PatternError::new("too many captures");
+        }
+
+        self.captures.push(Capture { start: s, length });
+
+        let result = self.match_pattern(s, p)?;
+        if result.is_none() {
+            self.captures.pop();
+        }
+
+        Ok(result)
+    }
+
+    fn end_capture(&mut self, s: usize, p: usize) -> PatternResult<Option<usize>> {
+        let index = match self
+            .captures
+            .iter()
+            .rposition(|capture| capture.length == CaptureLength::Unfinished)
+        {
+            Some(index) => index,
+            None => return PatternError::new("invalid pattern capture"),
+        };
+
+        self.captures[index].length = CaptureLength::Length(s - self.captures[index].start);
+
+        let result = self.match_pattern(s, p)?;
+        if result.is_none() {
+            self.captures[index].length = CaptureLength::Unfinished;
+        }
+
+        Ok(result)
+    }
+
+    /// Matches back-reference to a capture, eg: `%1`.
+    fn match_capture(&self, s: usize, digit: u8) -> PatternResult<Option<usize>> {
+        let number = (digit - b'0') as usize;
+        let capture = match number
+            .checked_sub(1)
+            .and_then(|index| self.captures.get(index))
+        {
+            Some(capture) if capture.length != CaptureLength::Unfinished => capture,
+            _ => return PatternError::new(&format!("invalid capture index %{}", number)),
+        };
+
+        match capture.length {
+            CaptureLength::Length(length) => {
+                let captured = &self.subject[capture.start..capture.start + length];
+                Ok(self.subject[s..]
+                    .starts_with(captured)
+                    .then_some(s + length))
+            }
+            // Position captures never match, same as in the reference implementation.
+            _ => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_strings(values: Vec<CaptureValue>) -> Vec<String> {
+        values
+            .into_iter()
+            .map(|value| match value {
+                CaptureValue::String(value) => String::from_utf8_lossy(value).into_owned(),
+                CaptureValue::Position(position) => position.to_string(),
+            })
+            .collect()
+    }
+
+    fn lua_match(subject: &str, pattern: &str) -> Option<Vec<String>> {
+        find(subject.as_bytes(), pattern.as_bytes(), 0)
+            .unwrap()
+            .map(|found| to_strings(found.values().unwrap()))
+    }
+
+    fn expect_match(subject: &str, pattern: &str, expected: &[&str]) {
+        assert_eq!(
+            lua_match(subject, pattern),
+            Some(expected.iter().map(|value| value.to_string()).collect()),
+            "matching {:?} with {:?}",
+            subject,
+            pattern
+        );
+    }
+
+    fn lua_gsub(
+        subject: &str,
+        pattern: &str,
+        replacement: &str,
+        max_replacements: Option<usize>,
+    ) -> PatternResult<(String, usize)> {
+        gsub(
+            subject.as_bytes(),
+            pattern.as_bytes(),
+            max_replacements,
+            |found: &Match| found.expand(replacement.as_bytes()).map(Some),
+        )
+        .map(|(output, count)| (String::from_utf8(output).unwrap(), count))
+    }
+
+    fn error_message(subject: &str, pattern: &str) -> String {
+        find(subject.as_bytes(), pattern.as_bytes(), 0)
+            .and_then(|found| found.map(|found| found.values()).transpose())
+            .unwrap_err()
+            .message
+    }
+
+    #[test]
+    fn should_match_character_classes() {
+        expect_match("hello world 123", "%a+", &["hello"]);
+        expect_match("hello 123", "%d+", &["123"]);
+        expect_match("0xBEEFy", "0x(%x+)", &["BEEF"]);
+        expect_match("foo_bar", "%w+", &["foo"]);
+        expect_match("x \t\x0b\ny", "%s+", &[" \t\x0b\n"]);
+        expect_match("abcDEF", "%u+", &["DEF"]);
+        expect_match("ABCdef", "%l+", &["def"]);
+        expect_match("a, b!", "%p", &[","]);
+        expect_match("a\x01b", "%c", &["\x01"]);
+        expect_match("  x=1 ", "%g+", &["x=1"]);
+        expect_match("abc123", "%D+", &["abc"]);
+        expect_match("123abc", "%A+", &["123"]);
+        expect_match("hello, world", "%W", &[","]);
+        expect_match("..ab", "%P+", &["ab"]);
+        expect_match("a.b", "%.", &["."]);
+        expect_match("a.b", ".", &["a"]);
+    }
+
+    #[test]
+    fn should_match_sets() {
+        expect_match("xyz_foo1 bar", "[%a_][%w_]*", &["xyz_foo1"]);
+        expect_match("key: value", "[^:]+", &["key"]);
+        expect_match("xxabcd", "[a-c]+", &["abc"]);
+        expect_match("a]b", "[]]", &["]"]);
+        expect_match("b-a", "[a-]+", &["-a"]);
+        expect_match("x^y", "[%^]", &["^"]);
+        expect_match("x^y", "[x^]+", &["x^"]);
+        expect_match("a1 b", "[^%s%d]+", &["a"]);
+    }
+
+    #[test]
+    fn should_match_quantifiers() {
+        expect_match("aaab", "a*", &["aaa"]);
+        expect_match("b", "a*", &[""]);
+        expect_match("aaab", "a+b", &["aaab"]);
+        expect_match("<a><b>", "<.->", &["<a>"]);
+        expect_match("<a><b>", "<.*>", &["<a><b>"]);
+        expect_match("color colour", "colou?r", &["color"]);
+        expect_match("-12", "^[+-]?%d+$", &["-12"]);
+        assert_eq!(lua_match("b", "a+"), None);
+    }
+
+    #[test]
+    fn should_match_anchors() {
+        expect_match("hello", "^h", &["h"]);
+        expect_match("hello", "lo$", &["lo"]);
+        expect_match("hel$lo", "l$l", &["l$l"]);
+        expect_match("", "", &[""]);
+        assert_eq!(lua_match("hello", "^e"), None);
+        assert_eq!(lua_match("hello", "l$"), None);
+
+        let found = find(b"hello", b"^l", 2).unwrap().unwrap();
+        assert_eq!((found.start, found.end), (2, 3));
+        assert!(find(b"hello", b"^l", 1).unwrap().is_none());
+        assert!(find(b"hello", b"", 6).unwrap().is_none());
+    }
+
+    #[test]
+    fn should_match_captures() {
+        expect_match("key = value", "(%w+)%s*=%s*(%w+)", &["key", "value"]);
+        expect_match("hello", "()ll()", &["3", "5"]);
+        expect_match("abc", "((a)(b))", &["ab", "a", "b"]);
+
+        let found = find(b"hello", b"l+", 0).unwrap().unwrap();
+        assert_eq!((found.start, found.end), (2, 4));
+        assert_eq!(found.captures(), Ok(vec![]));
+        assert_eq!(found.values(), Ok(vec![CaptureValue::String(b"ll")]));
+    }
+
+    #[test]
+    fn should_match_balanced_strings_and_frontiers() {
+        expect_match("f(a(b)c) d)", "%b()", &["(a(b)c)"]);
+        expect_match("(()", "%b()", &["()"]);
+        assert_eq!(lua_match("no", "%b()"), None);
+
+        expect_match("hello world", "%f[%w]%w+$", &["world"]);
+        let found = find(b"foo bar", b"%f[%W]", 0).unwrap().unwrap();
+        assert_eq!((found.start, found.end), (3, 3));
+        let found = find(b"foo", b"%f[%W]", 0).unwrap().unwrap();
+        assert_eq!((found.start, found.end), (3, 3));
+    }
+
+    #[test]
+    fn should_match_back_references() {
+        expect_match("say \"hi\" there", "([\"'])(.-)%1", &["\"", "hi"]);
+        expect_match("xabab", "(ab)%1", &["ab"]);
+        assert_eq!(lua_match("aa", "()%1"), None);
+    }
+
+    #[test]
+    fn should_match_byte_strings() {
+        let found = find(b"\x00\xff\xfe", b"[\xfe\xff]+", 0).unwrap().unwrap();
+        assert_eq!((found.start, found.end), (1, 3));
+        assert!(find(b"\x00", b".", 0).unwrap().is_some());
+        assert!(find(b"\x80", b"%a", 0).unwrap().is_none());
+    }
+
+    #[test]
+    fn should_iterate_matches() {
+        let words: Vec<_> = gmatch(b"one two three", b"%a+", 0)
+            .map(|found| to_strings(found.unwrap().values().unwrap()))
+            .collect();
+        assert_eq!(words, vec![vec!["one"], vec!["two"], vec!["three"]]);
+
+        let pairs: Vec<_> = gmatch(b"a=1, b=2", b"(%w+)=(%w+)", 0)
+            .map(|found| to_strings(found.unwrap().values().unwrap()))
+            .collect();
+        assert_eq!(pairs, vec![vec!["a", "1"], vec!["b", "2"]]);
+
+        let empty: Vec<_> = gmatch(b"abc", b"x*", 0)
+            .map(|found| found.unwrap().start)
+            .collect();
+        assert_eq!(empty, vec![0, 1, 2, 3]);
+
+        assert_eq!(gmatch(b"a^b", b"^b", 0).count(), 1);
+        assert_eq!(gmatch(b"abc", b"%a", 2).count(), 1);
+        assert_eq!(gmatch(b"abc", b"%a", 4).count(), 0);
+    }
+
+    #[test]
+    fn should_substitute_matches() {
+        assert_eq!(
+            lua_gsub("hello world", "(%w+)", "<%1>", None),
+            Ok((String::from("<hello> <world>"), 2))
+        );
+        assert_eq!(
+            lua_gsub("hello world", "%w+", "%0 %0", Some(1)),
+            Ok((String::from("hello hello world"), 1))
+        );
+        assert_eq!(
+            lua_gsub("abc", "", "-", None),
+            Ok((String::from("-a-b-c-"), 4))
+        );
+        assert_eq!(
+            lua_gsub("aaa", "^a", "b", None),
+            Ok((String::from("baa"), 1))
+        );
+        assert_eq!(
+            lua_gsub("100%", "%%", "%%%%", None),
+            Ok((String::from("100%%"), 1))
+        );
+        assert_eq!(
+            lua_gsub("abc", "%w", "%1", None),
+            Ok((String::from("abc"), 3))
+        );
+        assert_eq!(
+            lua_gsub("x = 1", "()=", "%1", None),
+            Ok((String::from("x 3 1"), 1))
+        );
+    }
+
+    #[test]
+    fn should_substitute_with_callback() {
+        let result = gsub(b"$name is $age", b"%$(%w+)", None, |found: &Match| {
+            Ok::<_, PatternError>(match found.capture(0)? {
+                CaptureValue::String(b"name") => Some(b"Lua".to_vec()),
+                _ => None,
+            })
+        });
+        assert_eq!(result, Ok((b"Lua is $age".to_vec(), 2)));
+
+        let result = gsub(b"abc", b"b", None, |_: &Match| {
+            PatternError::new::<Option<Vec<u8>>>("invalid replacement value (a table)")
+        });
+        assert_eq!(
+            result.unwrap_err().message,
+            "invalid replacement value (a table)"
+        );
+    }
+
+    #[test]
+    fn should_fail_on_malformed_patterns() {
+        for (pattern, message) in [
+            ("%", "malformed pattern (ends with '%')"),
+            ("[a", "malformed pattern (missing ']')"),
+            ("[", "malformed pattern (missing ']')"),
+            ("[^", "malformed pattern (missing ']')"),
+            ("[%]", "malformed pattern (missing ']')"),
+            ("%b", "malformed pattern (missing arguments to '%b')"),
+            ("%ba", "malformed pattern (missing arguments to '%b')"),
+            ("%f", "missing '[' after '%f' in pattern"),
+            ("%fa", "missing '[' after '%f' in pattern"),
+            ("%0", "invalid capture index %0"),
+            ("%1", "invalid capture index %1"),
+            ("(a)%2", "invalid capture index %2"),
+            ("(a%1)", "invalid capture index %1"),
+            (")", "invalid pattern capture"),
+            ("(a", "unfinished capture"),
+        ] {
+            assert_eq!(
+                error_message("aa", pattern),
+                message,
+                "pattern {:?}",
+                pattern
+            );
+        }
+
+        assert_eq!(
+            error_message("a", &"()".repeat(MAX_CAPTURES + 1)),
+            "too many captures"
+        );
+        assert_eq!(
+            lua_gsub("abc", "b", "%2", None).unwrap_err().message,
+            "invalid capture index %2"
+        );
+        assert_eq!(
+            lua_gsub("abc", "b", "%x", None).unwrap_err().message,
+            "invalid use of '%' in replacement string"
+        );
+        assert_eq!(
+            lua_gsub("abc", "b", "%", None).unwrap_err().message,
+            "invalid use of '%' in replacement string"
+        );
+    }
+
+    #[test]
+    fn should_limit_recursion_depth() {
+        expect_match(&"a".repeat(150), &"a?".repeat(150), &[&"a".repeat(150)]);
+        assert_eq!(
+            error_message(&"a".repeat(300), &"a?".repeat(300)),
+            "pattern too complex"
+        );
+        assert_eq!(
+            error_message("a", &"(".repeat(MAX_CAPTURES)),
+            "unfinished capture"
+        );
+    }
+}
@@ -1,6 +1,30 @@
-use crate::token::{Token, TokenType};
+use crate::{
+    chunk_id::chunk_id,
+    token::{Token, TokenType},
+};
 use std::fmt;
 
+#[derive(Debug, PartialEq)]
+pub struct ScanningError {
+    pub message: String,
+    pub line: usize,
+}
+
+impl ScanningError {
+    pub fn new<T>(message: &str, line: usize) -> Result<T, ScanningError> {
+        Err(ScanningError {
+            message: String::from(message),
+            line,
+        })
+    }
+
+    /// Formats the error as `chunkname:line: message`, with `chunk_name` following the
+    /// same conventions as in `ParsingError::to_lua_message`.
+    pub fn to_lua_message(&self, chunk_name: &str) -> String {
+        format!("{}:{}: {}", chunk_id(chunk_name), self.line, self.message)
+    }
+}
+
 pub struct Scanner<'s> {
     source: &'s str,
     tokens: Vec<Token<'s>>,
@@ -18,7 +42,7 @@ impl<'s> fmt::Debug for Scanner<'s> {
 }
 
 impl<'s> Scanner<'s> {
-    pub fn new(source: &str) -> Scanner {
+    pub fn new(source: &str) -> Scanner<'_> {
         Scanner {
             source,
             tokens: Vec::new(),
@@ -31,23 +55,25 @@ impl<'s> Scanner<'s> {
 
 impl<'s> Scanner<'s> {
     fn is_eof(&self) -> bool {
-        self.current >= self.source.chars().count()
+        self.current >= self.source.len()
     }
 
     fn is_numeric(&self, char: char) -> bool {
-        char >= '0' && char <= '9'
+        char.is_ascii_digit()
     }
 
     fn is_alpha(&self, char: char) -> bool {
-        (char >= 'A' && char <= 'Z') || (char >= 'a' && char <= 'z') || char == '_'
+        char.is_ascii_uppercase() || char.is_ascii_lowercase() || char == '_'
     }
 
     fn is_alphanumeric(&self, char: char) -> bool {
         self.is_numeric(char) || self.is_alpha(char)
     }
 
+    /// Returns byte at `index` as a char. Cursors are byte offsets, so slices of the
+    /// source stay valid, as tokens are delimited by ASCII characters only.
     fn char_at(&self, index: usize) -> Option<char> {
-        self.source.chars().nth(index)
+        self.source.as_bytes().get(index).map(|byte| *byte as char)
     }
 
     fn advance_cursor(&mut self, offset: usize) -> Option<char> {
@@ -56,25 +82,25 @@ impl<'s> Scanner<'s> {
         char
     }
 
+    fn is_closing_long_bracket(&self) -> bool {
+        self.char_at(self.current) == Some(']') && self.char_at(self.current + 1) == Some(']')
+    }
+
     fn consume_matching(&mut self, expected: char) -> bool {
         if self.is_eof() || self.char_at(self.current) != Some(expected) {
             return false;
         }
 
         self.current += 1;
-        return true;
+        true
     }
 
-    fn consume_comment(&mut self) {
-        if self.char_at(self.current + 1).unwrap_or('\0') == '['
-            && self.char_at(self.current + 2).unwrap_or('\0') == '['
-        {
-            self.advance_cursor(3); // consume -[[
+    fn consume_comment(&mut self) -> Result<(), ScanningError> {
+        if self.char_at(self.current) == Some('[') && self.char_at(self.current + 1) == Some('[') {
+            let start_line = self.line;
+            self.advance_cursor(2); // consume [[
 
-            while !self.is_eof()
-                && self.char_at(self.current) != Some(']')
-                && self.char_at(self.current + 1) != Some(']')
-            {
+            while !self.is_eof() && !self.is_closing_long_bracket() {
                 if self.char_at(self.current) == Some('\n') {
                     self.line += 1;
                 }
@@ -82,17 +108,24 @@ impl<'s> Scanner<'s> {
                 self.advance_cursor(1);
             }
 
-            if self.char_at(self.current) == Some('\n') {
-                self.line += 1;
-                self.advance_cursor(1);
+            if self.is_eof() {
+                return ScanningError::new(
+                    &format!(
+                        "unfinished long comment (starting at line {}) near <eof>",
+                        start_line
+                    ),
+                    self.line,
+                );
             }
 
-            self.advance_cursor(1); // consume ], second ] will be consumed on next iteration
+            self.advance_cursor(2); // consume ]]
         } else {
-            while !self.is_eof() && self.char_at(self.current + 1) != Some('\n') {
+            while !self.is_eof() && self.char_at(self.current) != Some('\n') {
                 self.advance_cursor(1);
             }
         }
+
+        Ok(())
     }
 
     fn add_token(&mut self, token_type: TokenType, literal: Option<&'s str>) {
@@ -101,40 +134,37 @@ impl<'s> Scanner<'s> {
         self.tokens.push(token);
     }
 
-    fn scan_literal_string(&mut self) -> Result<(), String> {
-        match self.char_at(self.current - 1) {
-            Some(delimiter) => {
-                while !self.is_eof()
-                    && (self.char_at(self.current) != Some(delimiter)
-                        || self.char_at(self.current) == Some('\\'))
-                    && self.char_at(self.current) != Some('\n')
-                {
-                    self.advance_cursor(1);
-                }
+    fn scan_literal_string(&mut self, delimiter: char) -> Result<(), ScanningError> {
+        while !self.is_eof()
+            && self.char_at(self.current) != Some(delimiter)
+            && self.char_at(self.current) != Some('\n')
+        {
+            // Escaped character, eg: `\'` or a line break, is a part of the string.
+            if self.advance_cursor(1) == Some('\\') && self.advance_cursor(1) == Some('\n') {
+                self.line += 1;
+            }
+        }
 
-                if self.is_eof() || self.char_at(self.current + 1) == Some('\n') {
-                    return Err(format!("unterminated string on line: {}", self.line));
-                }
+        if self.is_eof() {
+            return ScanningError::new("unfinished string near <eof>", self.line);
+        }
+        if self.char_at(self.current) == Some('\n') {
+            let lexeme = &self.source[self.start..self.current];
+            return ScanningError::new(&format!("unfinished string near '{}'", lexeme), self.line);
+        }
 
-                self.advance_cursor(1); // consume delimiter itself
+        self.advance_cursor(1); // consume delimiter itself
 
-                let literal = &self.source[self.start + 1..self.current - 1];
-                self.add_token(TokenType::LiteralString, Some(literal));
+        let literal = &self.source[self.start + 1..self.current - 1];
+        self.add_token(TokenType::LiteralString, Some(literal));
 
-                Ok(())
-            }
-            _ => Err(format!(
-                "cannot detect literal string delimiter on line: {}",
-                self.line
-            )),
-        }
+        Ok(())
     }
 
-    fn scan_multiline_literal_string(&mut self) -> Result<(), String> {
-        while !self.is_eof()
-            && self.char_at(self.current) != Some(']')
-            && self.char_at(self.current + 1) != Some(']')
-        {
+    fn scan_multiline_literal_string(&mut self) -> Result<(), ScanningError> {
+        let start_line = self.line;
+
+        while !self.is_eof() && !self.is_closing_long_bracket() {
             if self.char_at(self.current) == Some('\n') {
                 self.line += 1;
             }
@@ -142,15 +172,16 @@ impl<'s> Scanner<'s> {
         }
 
         if self.is_eof() {
-            return Err(format!("unterminated string on line: {}", self.line));
-        }
-
-        if self.char_at(self.current) == Some('\n') {
-            self.line += 1;
-            self.advance_cursor(1);
+            return ScanningError::new(
+                &format!(
+                    "unfinished long string (starting at line {}) near <eof>",
+                    start_line
+                ),
+                self.line,
+            );
         }
 
-        self.advance_cursor(1); // consume ], second ] will be consumed on next iteration
+        self.advance_cursor(2); // consume ]]
 
         let literal = &self.source[self.start + 2..self.current - 2];
         self.add_token(TokenType::LiteralString, Some(literal));
@@ -158,26 +189,43 @@ impl<'s> Scanner<'s> {
         Ok(())
     }
 
-    fn scan_numeral(&mut self) {
-        while self.is_numeric(self.char_at(self.current).unwrap_or('\0')) {
-            self.advance_cursor(1);
-        }
-
-        // TODO: handle hex - 0xff 0xBEBADA
-        // TODO: handle powers - 314.16e-2 0.31416E1 34e1 0x0.1E  0xA23p-4 0X1.921FB54442D18P+1
-
-        if self.char_at(self.current + 1) == Some('.')
-            && self.is_numeric(self.char_at(self.current + 2).unwrap_or('\0'))
+    /// Port of `read_numeral`: consumes everything which may continue the numeral and
+    /// validates it afterwards, so that eg: `1..2` or `0xfg` fail instead of being split
+    /// into several tokens.
+    fn scan_numeral(&mut self) -> Result<(), ScanningError> {
+        let mut exponent_marks = ['e', 'E'];
+        if self.char_at(self.start) == Some('0')
+            && matches!(self.char_at(self.current), Some('x' | 'X'))
         {
             self.advance_cursor(1);
+            exponent_marks = ['p', 'P'];
+        }
 
-            while self.is_numeric(self.char_at(self.current + 1).unwrap_or('\0')) {
+        while let Some(char) = self.char_at(self.current) {
+            if exponent_marks.contains(&char) {
+                self.advance_cursor(1);
+                if matches!(self.char_at(self.current), Some('+' | '-')) {
+                    self.advance_cursor(1);
+                }
+            } else if char.is_ascii_hexdigit() || char == '.' {
                 self.advance_cursor(1);
+            } else {
+                break;
             }
         }
 
+        // Numeral touching a letter is malformed, eg: `3x`.
+        if self.is_alpha(self.char_at(self.current).unwrap_or('\0')) {
+            self.advance_cursor(1);
+        }
+
         let literal = &self.source[self.start..self.current];
+        if !is_valid_numeral(literal) {
+            return ScanningError::new(&format!("malformed number near '{}'", literal), self.line);
+        }
+
         self.add_token(TokenType::Numeral, Some(literal));
+        Ok(())
     }
 
     fn scan_identifier(&mut self) {
@@ -213,12 +261,15 @@ impl<'s> Scanner<'s> {
         }
     }
 
-    pub fn scan_tokens(&mut self) -> Result<&Vec<Token>, String> {
+    pub fn scan_tokens(&mut self) -> Result<&Vec<Token<'_>>, ScanningError> {
         while !self.is_eof() {
             self.start = self.current;
 
             if let Some(char) = self.advance_cursor(1) {
                 match char {
+                    '.' if self.is_numeric(self.char_at(self.current).unwrap_or('\0')) => {
+                        self.scan_numeral()?;
+                    }
                     '.' => {
                         if self.consume_matching('.') {
                             if self.consume_matching('.') {
@@ -261,7 +312,7 @@ impl<'s> Scanner<'s> {
                     '+' => self.add_token(TokenType::Plus, None),
                     '-' => {
                         if self.consume_matching('-') {
-                            self.consume_comment();
+                            self.consume_comment()?;
                         } else {
                             self.add_token(TokenType::Minus, None)
                         }
@@ -288,9 +339,7 @@ impl<'s> Scanner<'s> {
                     '#' => self.add_token(TokenType::Hash, None),
                     '[' => {
                         if self.consume_matching('[') {
-                            if let Err(error) = self.scan_multiline_literal_string() {
-                                return Err(error);
-                            }
+                            self.scan_multiline_literal_string()?;
                         } else {
                             self.add_token(TokenType::LeftBracket, None);
                         }
@@ -301,9 +350,7 @@ impl<'s> Scanner<'s> {
                     '{' => self.add_token(TokenType::LeftBrace, None),
                     '}' => self.add_token(TokenType::RightBrace, None),
                     '\'' | '"' => {
-                        if let Err(error) = self.scan_literal_string() {
-                            return Err(error);
-                        }
+                        self.scan_literal_string(char)?;
                     }
                     ' ' | '\r' | '\t' => {
                         // Noop
@@ -311,13 +358,15 @@ impl<'s> Scanner<'s> {
                     '\n' => {
                         self.line += 1;
                     }
-                    char if self.is_numeric(char) => self.scan_numeral(),
+                    char if self.is_numeric(char) => self.scan_numeral()?,
                     char if self.is_alpha(char) => self.scan_identifier(),
                     _ => {
-                        return Err(format!(
-                            "unexpected character `{}` on line: {}",
-                            char, self.line
-                        ));
+                        // Report the whole character, not just its first byte.
+                        let char = self.source[self.start..].chars().next().unwrap_or(char);
+                        return ScanningError::new(
+                            &format!("unexpected character `{}`", char),
+                            self.line,
+                        );
                     }
                 }
             }
@@ -335,6 +384,34 @@ impl<'s> Scanner<'s> {
     }
 }
 
+/// Checks numeral against the formats accepted by `luaO_str2num`: decimal or hex digits
+/// with an optional fraction and exponent, eg: `3`, `3.`, `.5`, `1e-3`, `0xff`, `0x1p4`.
+fn is_valid_numeral(numeral: &str) -> bool {
+    let (digits, exponent_marks, is_digit): (_, _, fn(&char) -> bool) = match numeral
+        .strip_prefix("0x")
+        .or_else(|| numeral.strip_prefix("0X"))
+    {
+        Some(digits) => (digits, ['p', 'P'], char::is_ascii_hexdigit),
+        None => (numeral, ['e', 'E'], char::is_ascii_digit),
+    };
+
+    let (mantissa, exponent) = match digits.split_once(exponent_marks) {
+        Some((mantissa, exponent)) => (mantissa, Some(exponent)),
+        None => (digits, None),
+    };
+    let (integer, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+
+    let is_valid_mantissa = !(integer.is_empty() && fraction.is_empty())
+        && integer.chars().all(|char| is_digit(&char))
+        && fraction.chars().all(|char| is_digit(&char));
+    let is_valid_exponent = exponent.is_none_or(|exponent| {
+        let exponent = exponent.strip_prefix(['+', '-']).unwrap_or(exponent);
+        !exponent.is_empty() && exponent.chars().all(|char| char.is_ascii_digit())
+    });
+
+    is_valid_mantissa && is_valid_exponent
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -477,6 +554,150 @@ mod tests {
         );
     }
 
+    #[test]
+    fn should_scan_long_strings() {
+        assert_eq!(
+            Scanner::new("[[abc]] .. [[a]b\nc]]").scan_tokens(),
+            Ok(&vec![
+                Token::new(TokenType::LiteralString, "[[abc]]", 0, Some("abc"), 1),
+                Token::new(TokenType::DotDot, "..", 8, None, 1),
+                Token::new(
+                    TokenType::LiteralString,
+                    "[[a]b\nc]]",
+                    11,
+                    Some("a]b\nc"),
+                    2
+                ),
+                Token::new(TokenType::Eof, "", 20, None, 2)
+            ])
+        );
+    }
+
+    #[test]
+    fn should_scan_non_ascii_characters() {
+        assert_eq!(
+            Scanner::new("'é' .. 1").scan_tokens(),
+            Ok(&vec![
+                Token::new(TokenType::LiteralString, "'é'", 0, Some("é"), 1),
+                Token::new(TokenType::DotDot, "..", 5, None, 1),
+                Token::new(TokenType::Numeral, "1", 8, Some("1"), 1),
+                Token::new(TokenType::Eof, "", 9, None, 1)
+            ])
+        );
+
+        assert_eq!(
+            Scanner::new("\n€").scan_tokens(),
+            ScanningError::new("unexpected character `€`", 2)
+        );
+    }
+
+    #[test]
+    fn should_fail_on_unfinished_strings() {
+        for (source, message, line) in [
+            ("'abc", "unfinished string near <eof>", 1),
+            ("\"abc\\'", "unfinished string near <eof>", 1),
+            ("'abc\n'", "unfinished string near ''abc'", 1),
+            (
+                "[[abc]",
+                "unfinished long string (starting at line 1) near <eof>",
+                1,
+            ),
+            (
+                "\n[[abc\n",
+                "unfinished long string (starting at line 2) near <eof>",
+                3,
+            ),
+            (
+                "--[[ abc ]",
+                "unfinished long comment (starting at line 1) near <eof>",
+                1,
+            ),
+        ] {
+            assert_eq!(
+                Scanner::new(source).scan_tokens(),
+                ScanningError::new(message, line),
+                "scanning {:?}",
+                source
+            );
+        }
+    }
+
+    #[test]
+    fn should_scan_numerals() {
+        for numeral in [
+            "3",
+            "345",
+            "0xff",
+            "0XBEBADA",
+            "3.0",
+            "3.1416",
+            "314.16e-2",
+            "0.31416E1",
+            "34e1",
+            "3.",
+            ".5",
+            "0x0.1E",
+            "0xA23p-4",
+            "0X1.921FB54442D18P+1",
+        ] {
+            let mut scanner = Scanner::new(numeral);
+            let tokens = scanner.scan_tokens().unwrap();
+            assert_eq!(
+                tokens[0],
+                Token::new(TokenType::Numeral, numeral, 0, Some(numeral), 1),
+                "scanning {:?}",
+                numeral
+            );
+            assert_eq!(tokens.len(), 2, "scanning {:?}", numeral);
+        }
+
+        for numeral in [
+            "0x", "1e", "1e+", "1..2", "1.2.3", "0xfg", "3x", "0x1p", "08e",
+        ] {
+            assert_eq!(
+                Scanner::new(numeral).scan_tokens(),
+                ScanningError::new(&format!("malformed number near '{}'", numeral), 1),
+                "scanning {:?}",
+                numeral
+            );
+        }
+    }
+
+    #[test]
+    fn should_scan_escaped_delimiters_and_comments() {
+        assert_eq!(
+            Scanner::new("'a\\'b' -- c\n--[[ d ] ]]1.5").scan_tokens(),
+            Ok(&vec![
+                Token::new(TokenType::LiteralString, "'a\\'b'", 0, Some("a\\'b"), 1),
+                Token::new(TokenType::Numeral, "1.5", 23, Some("1.5"), 2),
+                Token::new(TokenType::Eof, "", 26, None, 2)
+            ])
+        );
+    }
+
+    #[test]
+    fn should_not_crash_on_short_sources() {
+        const PIECES: &[&str] = &[
+            "[", "]", "'", "\"", "\\", "\n", "-", "=", ".", "1", "a", " ", "é", "€",
+        ];
+
+        let mut sources = vec![String::new()];
+        for _ in 0..4 {
+            sources = sources
+                .iter()
+                .flat_map(|source| {
+                    PIECES
+                        .iter()
+                        .map(move |piece| format!("{}{}", source, piece))
+                })
+                .collect();
+
+            for source in &sources {
+                let _ = Scanner::new(source).scan_tokens();
+            }
+        }
+    }
+
     #[test]
     fn should_scan_vars() {
         assert_eq!(
@@ -0,0 +1,548 @@
+use crate::{
+    ast::{
+        is_right_associative, Args, Constant, Expression, Field, FunctionCall, Prefix,
+        TableConstructor, Variable,
+    },
+    token::{Token, TokenType},
+};
+use std::cmp::Ordering;
+
+/// Result of folding a single binary operation.
+enum Folded {
+    Left,
+    Right,
+    Constant(Constant),
+}
+
+/// Folds unary and binary expressions with constant operands, following the rules of
+/// `constfolding` from the reference implementation:
+/// - division and modulo by zero are left for the runtime to report,
+/// - bitwise operations require operands with an exact integer representation,
+/// - float results which are NaN or zero are not folded, so `-0.0` and NaN behave the
+///   same as at runtime.
+///
+/// Only operands which are constants themselves are folded. Identities like `x + 0`
+/// don't hold in Lua because of metamethods and string coercion.
+pub fn fold_constants(mut expression: Expression) -> Expression {
+    match &mut expression {
+        Expression::Unary { operator, right } => {
+            fold_in_place(right);
+            if let Some(constant) =
+                to_constant(right).and_then(|right| fold_unary(&operator.token_type, right))
+            {
+                return Expression::Constant(constant);
+            }
+        }
+        Expression::Binary { first, rest } => {
+            let first = fold_constants(first.take());
+            let rest = std::mem::take(rest)
+                .into_iter()
+                .map(|(operator, operand)| (operator, fold_constants(operand)))
+                .collect();
+            return fold_chain(first, rest);
+        }
+        Expression::TableConstructor(table_constructor) => {
+            fold_table_constructor(table_constructor)
+        }
+        Expression::Prefix(Prefix::Group(group)) => {
+            fold_in_place(group);
+
+            // Parentheses around a constant don't change its value.
+            if matches!(**group, Expression::Literal(_) | Expression::Constant(_)) {
+                return group.take();
+            }
+        }
+        Expression::Prefix(prefix) => fold_prefix(prefix),
+        Expression::Literal(_) | Expression::Constant(_) => {}
+    }
+
+    expression
+}
+
+fn fold_in_place(expression: &mut Expression) {
+    *expression = fold_constants(expression.take());
+}
+
+/// Folds chain of binary operations, which operands are already folded, in the order
+/// of its associativity. Folding stops at the first operation which can't be folded,
+/// because the result of the operation isn't a constant.
+fn fold_chain<'e>(
+    first: Expression<'e>,
+    rest: Vec<(&'e Token<'e>, Expression<'e>)>,
+) -> Expression<'e> {
+    if !rest
+        .first()
+        .is_some_and(|(operator, _)| is_right_associative(operator))
+    {
+        let mut left = first;
+        let mut rest = rest.into_iter();
+        while let Some((operator, right)) = rest.next() {
+            match fold_operation(&left, operator, to_constant(&right)) {
+                Some(Folded::Left) => {}
+                Some(Folded::Right) => left = right,
+                Some(Folded::Constant(constant)) => left = Expression::Constant(constant),
+                None => {
+                    let rest = std::iter::once((operator, right)).chain(rest).collect();
+                    return Expression::chain(left, rest);
+                }
+            }
+        }
+
+        return left;
+    }
+
+    let (mut operators, mut operands): (Vec<_>, Vec<_>) = rest.into_iter().unzip();
+    let mut right = operands.pop().unwrap();
+    operands.insert(0, first);
+
+    // Operations right of `right` which couldn't be folded, in reverse order. Only
+    // `and` and `or` selecting their left operand can still drop them.
+    let mut tail = Vec::new();
+    while let (Some(operator), Some(left)) = (operators.pop(), operands.pop()) {
+        let right_constant = if tail.is_empty() {
+            to_constant(&right)
+        } else {
+            None
+        };
+
+        match fold_operation(&left, operator, right_constant) {
+            Some(Folded::Left) => {
+                tail.clear();
+                right = left;
+            }
+            Some(Folded::Right) => {}
+            Some(Folded::Constant(constant)) => right = Expression::Constant(constant),
+            None => tail.push((operator, std::mem::replace(&mut right, left))),
+        }
+    }
+
+    tail.reverse();
+    Expression::chain(right, tail)
+}
+
+/// Folds `left operator right`, where `right` is given only if it is a constant.
+fn fold_operation(left: &Expression, operator: &Token, right: Option<Constant>) -> Option<Folded> {
+    let left = to_constant(left)?;
+
+    // Short-circuiting operators select one of the operands as is. The right one is
+    // only selected if it is a constant, because `true and f()` is truncated to a
+    // single value, while `f()` would not be.
+    let selects_left = match operator.token_type {
+        TokenType::And => Some(left.is_falsy()),
+        TokenType::Or => Some(!left.is_falsy()),
+        _ => None,
+    };
+
+    match selects_left {
+        Some(true) => Some(Folded::Left),
+        Some(false) => right.map(|_| Folded::Right),
+        None => fold_binary(&operator.token_type, left, right?).map(Folded::Constant),
+    }
+}
+
+fn fold_prefix(prefix: &mut Prefix) {
+    match prefix {
+        Prefix::Variable(variable) => fold_variable(variable),
+        Prefix::FunctionCall(
+            FunctionCall::FunctionCall { callee, args }
+            | FunctionCall::MethodCall { callee, args, .. },
+        ) => {
+            fold_prefix(callee);
+            fold_args(args);
+        }
+        // Groups can only be removed as expressions, a prefix needs to stay a prefix.
+        Prefix::Group(expression) => fold_in_place(expression),
+    }
+}
+
+fn fold_variable(variable: &mut Variable) {
+    match variable {
+        Variable::Identifier(_) => {}
+        Variable::MemberAccess { reference, .. } => fold_prefix(reference),
+        Variable::ExpressionMemberAccess { reference, member } => {
+            fold_prefix(reference);
+            fold_in_place(member);
+        }
+    }
+}
+
+fn fold_args(args: &mut Args) {
+    match args {
+        Args::ExpressionList(expressions) => expressions.iter_mut().for_each(fold_in_place),
+        Args::TableConstructor(table_constructor) => fold_table_constructor(table_constructor),
+        Args::LiteralString(_) => {}
+    }
+}
+
+fn fold_table_constructor(table_constructor: &mut TableConstructor) {
+    for field in table_constructor.fields.iter_mut() {
+        match field {
+            Field::Expression { key, value } => {
+                fold_in_place(key);
+                fold_in_place(value);
+            }
+            Field::Normal { value, .. } | Field::Anonymous { value } => fold_in_place(value),
+        }
+    }
+}
+
+fn to_constant(expression: &Expression) -> Option<Constant> {
+    match expression {
+        Expression::Literal(token) => Constant::from_literal(token),
+        Expression::Constant(constant) => Some(constant.clone()),
+        _ => None,
+    }
+}
+
+fn fold_unary(operator: &TokenType, right: Constant) -> Option<Constant> {
+    let result = match (operator, right) {
+        (TokenType::Not, right) => Constant::Boolean(right.is_falsy()),
+        (TokenType::Hash, Constant::String(value)) => Constant::Integer(value.len() as i64),
+        (TokenType::Minus, Constant::Integer(value)) => Constant::Integer(value.wrapping_neg()),
+        (TokenType::Minus, Constant::Float(value)) => Constant::Float(-value),
+        (TokenType::Tilde, right) => Constant::Integer(!right.to_integer()?),
+        _ => return None,
+    };
+
+    validate_result(result)
+}
+
+fn fold_binary(operator: &TokenType, left: Constant, right: Constant) -> Option<Constant> {
+    let result = match operator {
+        TokenType::Plus
+        | TokenType::Minus
+        | TokenType::Star
+        | TokenType::Slash
+        | TokenType::SlashSlash
+        | TokenType::Percent
+        | TokenType::Caret => fold_arithmetic(operator, &left, &right)?,
+        TokenType::Ampersand
+        | TokenType::Pipe
+        | TokenType::Tilde
+        | TokenType::LessLess
+        | TokenType::GreaterGreater => {
+            fold_bitwise(operator, left.to_integer()?, right.to_integer()?)?
+        }
+        TokenType::DotDot => Constant::String(format!(
+            "{}{}",
+            to_concat_string(left)?,
+            to_concat_string(right)?
+        )),
+        TokenType::EqualEqual => Constant::Boolean(are_equal(&left, &right)),
+        TokenType::TildeEqual => Constant::Boolean(!are_equal(&left, &right)),
+        TokenType::Less => Constant::Boolean(compare(&left, &right)? == Some(Ordering::Less)),
+        TokenType::LessEqual => Constant::Boolean(matches!(
+            compare(&left, &right)?,
+            Some(Ordering::Less | Ordering::Equal)
+        )),
+        TokenType::Greater => Constant::Boolean(compare(&left, &right)? == Some(Ordering::Greater)),
+        TokenType::GreaterEqual => Constant::Boolean(matches!(
+            compare(&left, &right)?,
+            Some(Ordering::Greater | Ordering::Equal)
+        )),
+        _ => return None,
+    };
+
+    validate_result(result)
+}
+
+fn fold_arithmetic(operator: &TokenType, left: &Constant, right: &Constant) -> Option<Constant> {
+    // Division by zero is left for the runtime, which raises an error for integers.
+    if matches!(
+        operator,
+        TokenType::Slash | TokenType::SlashSlash | TokenType::Percent
+    ) && right.to_float()? == 0.0
+    {
+        return None;
+    }
+
+    if let (Constant::Integer(left), Constant::Integer(right)) = (left, right) {
+        let result = match operator {
+            TokenType::Plus => Some(left.wrapping_add(*right)),
+            TokenType::Minus => Some(left.wrapping_sub(*right)),
+            TokenType::Star => Some(left.wrapping_mul(*right)),
+            TokenType::SlashSlash => Some(integer_floor_division(*left, *right)),
+            TokenType::Percent => Some(integer_modulo(*left, *right)),
+            _ => None,
+        };
+
+        if let Some(result) = result {
+            return Some(Constant::Integer(result));
+        }
+    }
+
+    let (left, right) = (left.to_float()?, right.to_float()?);
+    let result = match operator {
+        TokenType::Plus => left + right,
+        TokenType::Minus => left - right,
+        TokenType::Star => left * right,
+        TokenType::Slash => left / right,
+        TokenType::SlashSlash => (left / right).floor(),
+        TokenType::Percent => float_modulo(left, right),
+        TokenType::Caret => left.powf(right),
+        _ => return None,
+    };
+
+    Some(Constant::Float(result))
+}
+
+fn fold_bitwise(operator: &TokenType, left: i64, right: i64) -> Option<Constant> {
+    let result = match operator {
+        TokenType::Ampersand => left & right,
+        TokenType::Pipe => left | right,
+        TokenType::Tilde => left ^ right,
+        TokenType::LessLess => shift_left(left, right),
+        TokenType::GreaterGreater => shift_left(left, right.wrapping_neg()),
+        _ => return None,
+    };
+
+    Some(Constant::Integer(result))
+}
+
+/// Port of `luaV_idiv`, rounds the quotient towards minus infinity.
+fn integer_floor_division(left: i64, right: i64) -> i64 {
+    let quotient = left.wrapping_div(right);
+    if left.wrapping_rem(right) != 0 && (left ^ right) < 0 {
+        quotient - 1
+    } else {
+        quotient
+    }
+}
+
+/// Port of `luaV_mod`, the result has the same sign as the divisor.
+fn integer_modulo(left: i64, right: i64) -> i64 {
+    let remainder = left.wrapping_rem(right);
+    if remainder != 0 && (remainder ^ right) < 0 {
+        remainder + right
+    } else {
+        remainder
+    }
+}
+
+/// Port of `luai_nummod`, the result has the same sign as the divisor.
+fn float_modulo(left: f64, right: f64) -> f64 {
+    let remainder = left % right;
+    if (remainder > 0.0 && right < 0.0) || (remainder < 0.0 && right > 0.0) {
+        remainder + right
+    } else {
+        remainder
+    }
+}
+
+/// Port of `luaV_shiftl`, negative displacement shifts to the right.
+fn shift_left(value: i64, displacement: i64) -> i64 {
+    if displacement <= -64 || displacement >= 64 {
+        0
+    } else if displacement < 0 {
+        ((value as u64) >> -displacement) as i64
+    } else {
+        ((value as u64) << displacement) as i64
+    }
+}
+
+/// Floats are formatted with `%.14g` at runtime, so only strings and integers are folded.
+fn to_concat_string(constant: Constant) -> Option<String> {
+    match constant {
+        Constant::String(value) => Some(value),
+        Constant::Integer(value) => Some(value.to_string()),
+        _ => None,
+    }
+}
+
+fn are_equal(left: &Constant, right: &Constant) -> bool {
+    match (left, right) {
+        (Constant::Integer(_) | Constant::Float(_), Constant::Integer(_) | Constant::Float(_)) => {
+            compare_numbers(left, right) == Some(Ordering::Equal)
+        }
+        _ => left == right,
+    }
+}
+
+/// Returns `None` if the operands cannot be ordered at compile time and `Some(None)` if
+/// they are unordered, eg: when one of them is NaN.
+fn compare(left: &Constant, right: &Constant) -> Option<Option<Ordering>> {
+    match (left, right) {
+        (Constant::Integer(_) | Constant::Float(_), Constant::Integer(_) | Constant::Float(_)) => {
+            Some(compare_numbers(left, right))
+        }
+        // Strings are compared with `strcoll`, which depends on the runtime locale.
+        _ => None,
+    }
+}
+
+/// Compares numbers by their mathematical values, without rounding integers to floats.
+fn compare_numbers(left: &Constant, right: &Constant) -> Option<Ordering> {
+    match (left, right) {
+        (Constant::Integer(left), Constant::Integer(right)) => Some(left.cmp(right)),
+        (Constant::Float(left), Constant::Float(right)) => left.partial_cmp(right),
+        (Constant::Integer(left), Constant::Float(right)) => {
+            compare_integer_with_float(*left, *right)
+        }
+        (Constant::Float(left), Constant::Integer(right)) => {
+            compare_integer_with_float(*right, *left).map(Ordering::reverse)
+        }
+        _ => None,
+    }
+}
+
+fn compare_integer_with_float(left: i64, right: f64) -> Option<Ordering> {
+    if right.is_nan() {
+        None
+    } else if right >= 2f64.powi(63) {
+        Some(Ordering::Less)
+    } else if right < -(2f64.powi(63)) {
+        Some(Ordering::Greater)
+    } else {
+        let truncated = right.trunc();
+        Some(
+            left.cmp(&(truncated as i64))
+                .then(0f64.partial_cmp(&(right - truncated))?),
+        )
+    }
+}
+
+/// Float results which are NaN or zero are not folded, see `constfolding`.
+fn validate_result(result: Constant) -> Option<Constant> {
+    match result {
+        Constant::Float(value) if value.is_nan() || value == 0.0 => None,
+        result => Some(result),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parser::Parser, scanner::Scanner, visitor::debug_visitor::DebugVisitor};
+
+    fn expect_source_to_fold_into(source: &str, expected: &str) {
+        let mut scanner = Scanner::new(source);
+        let tokens = scanner.scan_tokens().unwrap();
+        let parser = Parser::new(tokens);
+        let ast = fold_constants(parser.parse().unwrap());
+        let output = ast.visit(&DebugVisitor);
+        assert_eq!(&output[..], expected, "folding `{}`", source);
+    }
+
+    #[test]
+    fn should_fold_arithmetic() {
+        expect_source_to_fold_into("60 * 60 * 24", "`86400`");
+        expect_source_to_fold_into("10 - 2 - 3", "`5`");
+        expect_source_to_fold_into("2 + 3 * 4", "`14`");
+        expect_source_to_fold_into("(2 + 3) * 4", "`20`");
+        expect_source_to_fold_into("1 / 2", "`0.5`");
+        expect_source_to_fold_into("4 / 2", "`2.0`");
+        expect_source_to_fold_into("2 ^ 10", "`1024.0`");
+        expect_source_to_fold_into("-2 ^ 2", "`-4.0`");
+        expect_source_to_fold_into("7 // 2", "`3`");
+        expect_source_to_fold_into("-7 // 2", "`-4`");
+        expect_source_to_fold_into("7 // -2", "`-4`");
+        expect_source_to_fold_into("-7 % 3", "`2`");
+        expect_source_to_fold_into("7 % -3", "`-2`");
+        expect_source_to_fold_into("(1 / 2) % -1", "`-0.5`");
+        expect_source_to_fold_into("(3 / 2) // 1", "`1.0`");
+        expect_source_to_fold_into("9223372036854775807 + 1", "`-9223372036854775808`");
+        expect_source_to_fold_into("9223372036854775808", "`9223372036854775808`");
+        expect_source_to_fold_into("-9223372036854775808", "`-9.223372036854776e18`");
+        expect_source_to_fold_into("foo + 1 * 2", "[+ l=foo r=`2`]");
+        expect_source_to_fold_into("0x10 + 1", "`17`");
+        expect_source_to_fold_into("0xffffffffffffffff + 0", "`-1`");
+        expect_source_to_fold_into("1e2 + .5", "`100.5`");
+        expect_source_to_fold_into("0x1p4", "`0x1p4`");
+    }
+
+    #[test]
+    fn should_not_fold_division_by_zero() {
+        expect_source_to_fold_into("1 // 0", "[// l=`1` r=`0`]");
+        expect_source_to_fold_into("1 % 0", "[% l=`1` r=`0`]");
+        expect_source_to_fold_into("1 / 0", "[/ l=`1` r=`0`]");
+        expect_source_to_fold_into("1 % (1 - 1)", "[% l=`1` r=`0`]");
+    }
+
+    #[test]
+    fn should_not_fold_nan_or_zero_floats() {
+        expect_source_to_fold_into("-(0 / 1)", "[- r=([/ l=`0` r=`1`])]");
+        expect_source_to_fold_into("0 * (1 / 2)", "[* l=`0` r=`0.5`]");
+        expect_source_to_fold_into("-0", "`0`");
+    }
+
+    #[test]
+    fn should_fold_bitwise_operations() {
+        expect_source_to_fold_into("1 << 4 | 1", "`17`");
+        expect_source_to_fold_into("1 << 64", "`0`");
+        expect_source_to_fold_into("1 << 63", "`-9223372036854775808`");
+        expect_source_to_fold_into("-1 >> 1", "`9223372036854775807`");
+        expect_source_to_fold_into("1 >> -2", "`4`");
+        expect_source_to_fold_into("6 & 3 ~ 1", "`3`");
+        expect_source_to_fold_into("~0", "`-1`");
+        expect_source_to_fold_into("(4 / 2) | 1", "`3`");
+        expect_source_to_fold_into("(1 / 2) | 1", "[| l=`0.5` r=`1`]");
+        expect_source_to_fold_into("'1' | 1", "[| l=`'1'` r=`1`]");
+    }
+
+    #[test]
+    fn should_fold_concatenation() {
+        expect_source_to_fold_into("'prefix' .. 'suffix'", "`\"prefixsuffix\"`");
+        expect_source_to_fold_into("'a' .. 1 .. \"b\"", "`\"a1b\"`");
+        expect_source_to_fold_into("'a' .. 1 / 2", "[.. l=`'a'` r=`0.5`]");
+        expect_source_to_fold_into("'a\\n' .. 'b'", "[.. l=`'a\\n'` r=`'b'`]");
+        expect_source_to_fold_into("#'abc'", "`3`");
+        expect_source_to_fold_into("#[[abc]]", "[# r=`[[abc]]`]");
+        expect_source_to_fold_into("[[abc]] .. 'x'", "[.. l=`[[abc]]` r=`'x'`]");
+    }
+
+    #[test]
+    fn should_fold_comparisons() {
+        expect_source_to_fold_into("1 < 2", "`true`");
+        expect_source_to_fold_into("2 <= 1", "`false`");
+        expect_source_to_fold_into("4 / 2 == 2", "`true`");
+        expect_source_to_fold_into("9007199254740993 == 2 ^ 53 + 1", "`false`");
+        expect_source_to_fold_into("9007199254740993 > 2 ^ 53", "`true`");
+        expect_source_to_fold_into("'a' == 'a'", "`true`");
+        expect_source_to_fold_into("'a' ~= 1", "`true`");
+        expect_source_to_fold_into("nil == false", "`false`");
+        expect_source_to_fold_into("'a' < 'b'", "[< l=`'a'` r=`'b'`]");
+    }
+
+    #[test]
+    fn should_fold_logical_operators() {
+        expect_source_to_fold_into("not nil", "`true`");
+        expect_source_to_fold_into("not 0", "`false`");
+        expect_source_to_fold_into("nil and foo()", "`nil`");
+        expect_source_to_fold_into("1 or foo()", "`1`");
+        expect_source_to_fold_into("true and 1 + 1", "`2`");
+        expect_source_to_fold_into("false or 'x'", "`'x'`");
+        expect_source_to_fold_into("true and foo()", "[and l=`true` r=[foo a:]]");
+        expect_source_to_fold_into("foo and 1 + 1", "[and l=foo r=`2`]");
+    }
+
+    #[test]
+    fn should_fold_nested_expressions() {
+        expect_source_to_fold_into(
+            "{ [1 + 1] = 2 * 3, foo = -(1), 'a' .. 'b' }",
+            "Tc[`2`=`6` `foo`=`-1` ?=`\"ab\"` ]",
+        );
+        expect_source_to_fold_into("foo[1 + 2].bar(3 * 4)", "[foo[`3`].bar a:`12`, ]");
+        expect_source_to_fold_into("(1 + 2):baz()", "[(`3`):baz a:]");
+        expect_source_to_fold_into("(foo(1 + 1))", "([foo a:`2`, ])");
+    }
+
+    #[test]
+    fn should_fold_long_operator_chains() {
+        expect_source_to_fold_into(&vec!["1"; 100_000].join(" + "), "`100000`");
+        expect_source_to_fold_into(&vec!["2"; 100_000].join(" // "), "`0`");
+        expect_source_to_fold_into(
+            &vec!["'a'"; 100_000].join(" .. "),
+            &format!("`{:?}`", "a".repeat(100_000)),
+        );
+    }
+
+    #[test]
+    fn should_fold_chains_up_to_first_non_constant_operation() {
+        expect_source_to_fold_into("1 + 2 + foo + 3", "[+ l=[+ l=`3` r=foo] r=`3`]");
+        expect_source_to_fold_into("foo .. 'a' .. 'b'", "[.. l=foo r=`\"ab\"`]");
+        expect_source_to_fold_into("'a' .. 'b' .. foo", "[.. l=`'a'` r=[.. l=`'b'` r=foo]]");
+        expect_source_to_fold_into("false and foo and bar", "`false`");
+        expect_source_to_fold_into("foo and false and bar", "[and l=foo r=`false`]");
+        expect_source_to_fold_into("nil or foo or 1", "[or l=`nil` r=[or l=foo r=`1`]]");
+    }
+}
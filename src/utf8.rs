@@ -0,0 +1,610 @@
+//! Port of `lutf8lib.c` of the reference implementation, which backs the `utf8` library.
+//!
+//! Functions work on byte strings and take string positions like in Lua: 1-based, with
+//! negative positions counting back from the end. Returned positions are 1-based too.
+
+/// Maximum code point of Unicode, larger ones are accepted only in lax mode.
+pub const MAX_UNICODE: u32 = 0x10FFFF;
+
+/// Maximum value which can be encoded by the original UTF-8 scheme, in up to 6 bytes.
+pub const MAX_UTF: u32 = 0x7FFFFFFF;
+
+/// Pattern matching exactly one UTF-8 byte sequence, `utf8.charpattern`.
+pub const CHAR_PATTERN: &[u8] = b"[\0-\x7F\xC2-\xFD][\x80-\xBF]*";
+
+const INVALID_CODE: &str = "invalid UTF-8 code";
+
+pub type Utf8Result<T> = Result<T, Utf8Error>;
+
+#[derive(Debug, PartialEq)]
+pub struct Utf8Error {
+    pub message: String,
+}
+
+impl Utf8Error {
+    pub fn new<T>(message: &str) -> Utf8Result<T> {
+        Err(Utf8Error {
+            message: String::from(message),
+        })
+    }
+}
+
+/// Result of `utf8.len`.
+#[derive(Debug, PartialEq)]
+pub enum Length {
+    Characters(usize),
+    /// Position of the first byte which doesn't start a valid byte sequence.
+    InvalidByte(usize),
+}
+
+fn is_continuation(byte: u8) -> bool {
+    byte & 0xC0 == 0x80
+}
+
+/// Port of `u_posrelat`: translates negative position to one counting from the start.
+fn relative_position(position: i64, length: usize) -> i64 {
+    if position >= 0 {
+        position
+    } else if position.unsigned_abs() > length as u64 {
+        0
+    } else {
+        length as i64 + position + 1
+    }
+}
+
+/// Port of `utf8_decode`: decodes byte sequence at the start of `bytes`, returning code
+/// point and length of the sequence, or `None` if the sequence is invalid. Strict mode
+/// also rejects surrogates and code points above [`MAX_UNICODE`].
+pub fn decode(bytes: &[u8], strict: bool) -> Option<(u32, usize)> {
+    // Bytes past the end read as `\0`, same as the terminator of Lua strings.
+    let byte_at = |index: usize| bytes.get(index).copied().unwrap_or(0);
+
+    let mut first = byte_at(0) as u32;
+    let mut code = first;
+    let mut count = 0;
+
+    if first >= 0x80 {
+        code = 0;
+
+        // Each set bit after the highest one announces a continuation byte.
+        while first & 0x40 != 0 {
+            count += 1;
+            let byte = byte_at(count);
+            if !is_continuation(byte) {
+                return None;
+            }
+
+            code = (code << 6) | (byte & 0x3F) as u32;
+            first <<= 1;
+        }
+
+        // Minimum code point of each sequence length, longer sequences are overlong.
+        const LIMITS: [u32; 6] = [u32::MAX, 0x80, 0x800, 0x10000, 0x200000, 0x4000000];
+        if count > 5 {
+            return None;
+        }
+
+        code |= (first & 0x7F) << (count * 5);
+        if code > MAX_UTF || code < LIMITS[count] {
+            return None;
+        }
+    }
+
+    if strict && (code > MAX_UNICODE || (0xD800..=0xDFFF).contains(&code)) {
+        return None;
+    }
+
+    Some((code, count + 1))
+}
+
+/// Port of `utfchar`, same as `utf8.char(...)`: encodes code points into a byte string,
+/// using up to 6 bytes for values above [`MAX_UNICODE`].
+pub fn encode(codes: &[i64]) -> Utf8Result<Vec<u8>> {
+    let mut output = Vec::with_capacity(codes.len());
+
+    for (index, &code) in codes.iter().enumerate() {
+        if code as u64 > MAX_UTF as u64 {
+            return Utf8Error::new(&format!(
+                "bad argument #{} to 'utf8.char' (value out of range)",
+                index + 1
+            ));
+        }
+
+        encode_code(code as u32, &mut output);
+    }
+
+    Ok(output)
+}
+
+/// Port of `luaO_utf8esc`.
+fn encode_code(mut code: u32, output: &mut Vec<u8>) {
+    if code < 0x80 {
+        output.push(code as u8);
+        return;
+    }
+
+    let mut continuations = Vec::with_capacity(5);
+    // Maximum value which fits into the first byte.
+    let mut first_byte_max = 0x3F;
+
+    loop {
+        continuations.push(0x80 | (code & 0x3F) as u8);
+        code >>= 6;
+        first_byte_max >>= 1;
+
+        if code <= first_byte_max {
+            break;
+        }
+    }
+
+    output.push(((!first_byte_max << 1) | code) as u8);
+    output.extend(continuations.iter().rev());
+}
+
+/// Port of `utflen`, same as `utf8.len(bytes, start, end, lax)`: counts characters which
+/// start between positions `start` and `end`, inclusive.
+pub fn len(bytes: &[u8], start: Option<i64>, end: Option<i64>, lax: bool) -> Utf8Result<Length> {
+    let start = relative_position(start.unwrap_or(1), bytes.len());
+    let end = relative_position(end.unwrap_or(-1), bytes.len());
+
+    if start < 1 || start - 1 > bytes.len() as i64 {
+        return Utf8Error::new("bad argument #2 to 'utf8.len' (initial position out of bounds)");
+    }
+    if end > bytes.len() as i64 {
+        return Utf8Error::new("bad argument #3 to 'utf8.len' (final position out of bounds)");
+    }
+
+    let mut position = (start - 1) as usize;
+    let mut count = 0;
+
+    while (position as i64) < end {
+        match decode(&bytes[position..], !lax) {
+            Some((_, length)) => position += length,
+            None => return Ok(Length::InvalidByte(position + 1)),
+        }
+
+        count += 1;
+    }
+
+    Ok(Length::Characters(count))
+}
+
+/// Port of `codepoint`, same as `utf8.codepoint(bytes, start, end, lax)`: decodes all
+/// characters which start between positions `start` and `end`, inclusive.
+pub fn codepoint(
+    bytes: &[u8],
+    start: Option<i64>,
+    end: Option<i64>,
+    lax: bool,
+) -> Utf8Result<Vec<u32>> {
+    let start = relative_position(start.unwrap_or(1), bytes.len());
+    let end = relative_position(end.unwrap_or(start), bytes.len());
+
+    if start < 1 {
+        return Utf8Error::new("bad argument #2 to 'utf8.codepoint' (out of bounds)");
+    }
+    if end > bytes.len() as i64 {
+        return Utf8Error::new("bad argument #3 to 'utf8.codepoint' (out of bounds)");
+    }
+    if start > end {
+        return Ok(Vec::new());
+    }
+    if end - start >= i32::MAX as i64 {
+        return Utf8Error::new("string slice too long");
+    }
+
+    let mut codes = Vec::new();
+    let mut position = (start - 1) as usize;
+
+    while position < end as usize {
+        match decode(&bytes[position..], !lax) {
+            Some((code, length)) => {
+                codes.push(code);
+                position += length;
+            }
+            None => return Utf8Error::new(INVALID_CODE),
+        }
+    }
+
+    Ok(codes)
+}
+
+/// Port of `byteoffset`, same as `utf8.offset(bytes, n, start)`: finds position where
+/// `n`-th character counting from position `start` begins, where 0 is the character
+/// containing `start`. Returns `None` if there is no such character.
+pub fn offset(bytes: &[u8], n: i64, start: Option<i64>) -> Utf8Result<Option<usize>> {
+    let is_continuation_at = |position: usize| {
+        bytes
+            .get(position)
+            .is_some_and(|&byte| is_continuation(byte))
+    };
+
+    let default_start = if n >= 0 { 1 } else { bytes.len() as i64 + 1 };
+    let start = relative_position(start.unwrap_or(default_start), bytes.len());
+
+    if start < 1 || start - 1 > bytes.len() as i64 {
+        return Utf8Error::new("bad argument #3 to 'utf8.offset' (position out of bounds)");
+    }
+
+    let mut position = (start - 1) as usize;
+    let mut n = n;
+
+    if n == 0 {
+        while position > 0 && is_continuation_at(position) {
+            position -= 1;
+        }
+    } else if is_continuation_at(position) {
+        return Utf8Error::new("initial position is a continuation byte");
+    } else if n < 0 {
+        while n < 0 && position > 0 {
+            position -= 1;
+            while position > 0 && is_continuation_at(position) {
+                position -= 1;
+            }
+
+            n += 1;
+        }
+    } else {
+        // First character is the one at `start`.
+        n -= 1;
+        while n > 0 && position < bytes.len() {
+            position += 1;
+            while is_continuation_at(position) {
+                position += 1;
+            }
+
+            n -= 1;
+        }
+    }
+
+    Ok((n == 0).then_some(position + 1))
+}
+
+/// Port of `iter_codes`, same as `utf8.codes(bytes, lax)`: iterates over positions and
+/// code points of characters.
+pub fn codes(bytes: &[u8], lax: bool) -> Utf8Result<Codes<'_>> {
+    if bytes.first().is_some_and(|&byte| is_continuation(byte)) {
+        return Utf8Error::new("bad argument #1 to 'utf8.codes' (invalid UTF-8 code)");
+    }
+
+    Ok(Codes {
+        bytes,
+        position: 0,
+        strict: !lax,
+    })
+}
+
+pub struct Codes<'s> {
+    bytes: &'s [u8],
+    /// Position of the previous character, 0 before the first one.
+    position: usize,
+    strict: bool,
+}
+
+impl<'s> Iterator for Codes<'s> {
+    type Item = Utf8Result<(usize, u32)>;
+
+    /// Port of `iter_aux`.
+    fn next(&mut self) -> Option<Self::Item> {
+        let bytes = self.bytes;
+        let mut index = self.position;
+
+        while index < bytes.len() && is_continuation(bytes[index]) {
+            index += 1;
+        }
+
+        if index >= bytes.len() {
+            return None;
+        }
+
+        match decode(&bytes[index..], self.strict) {
+            // Character must not be followed by a stray continuation byte.
+            Some((code, length))
+                if !bytes
+                    .get(index + length)
+                    .is_some_and(|&byte| is_continuation(byte)) =>
+            {
+                self.position = index + 1;
+                Some(Ok((index + 1, code)))
+            }
+            _ => {
+                self.position = bytes.len();
+                Some(Utf8Error::new(INVALID_CODE))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pattern;
+
+    // Expected values come from Lua 5.4.
+
+    #[test]
+    fn should_decode_byte_sequences() {
+        assert_eq!(decode(b"a", true), Some((97, 1)));
+        assert_eq!(decode("é".as_bytes(), true), Some((233, 2)));
+        assert_eq!(decode("中".as_bytes(), true), Some((0x4E2D, 3)));
+        assert_eq!(decode(b"\xf4\x8f\xbf\xbf", true), Some((MAX_UNICODE, 4)));
+        assert_eq!(decode(b"", true), Some((0, 1)));
+
+        // Overlong, truncated and stray bytes.
+        assert_eq!(decode(b"\xc0\x80", false), None);
+        assert_eq!(decode(b"\xe4\xbd", false), None);
+        assert_eq!(decode(b"\x80", false), None);
+        assert_eq!(decode(b"\xfe\x80\x80\x80\x80\x80\x80", false), None);
+
+        // Surrogates and values above Unicode are accepted only in lax mode.
+        assert_eq!(decode(b"\xed\xa0\x80", true), None);
+        assert_eq!(decode(b"\xed\xa0\x80", false), Some((0xD800, 3)));
+        assert_eq!(decode(b"\xf4\x90\x80\x80", true), None);
+        assert_eq!(decode(b"\xf4\x90\x80\x80", false), Some((0x110000, 4)));
+        assert_eq!(
+            decode(b"\xfd\xbf\xbf\xbf\xbf\xbf", false),
+            Some((MAX_UTF, 6))
+        );
+    }
+
+    #[test]
+    fn should_encode_code_points() {
+        for (code, expected) in [
+            (0, b"\x00".as_slice()),
+            (0x7F, b"\x7f"),
+            (0x80, b"\xc2\x80"),
+            (0x7FF, b"\xdf\xbf"),
+            (0x800, b"\xe0\xa0\x80"),
+            (0xFFFF, b"\xef\xbf\xbf"),
+            (0x10000, b"\xf0\x90\x80\x80"),
+            (0x10FFFF, b"\xf4\x8f\xbf\xbf"),
+            (0x200000, b"\xf8\x88\x80\x80\x80"),
+            (0x3FFFFFF, b"\xfb\xbf\xbf\xbf\xbf"),
+            (0x4000000, b"\xfc\x84\x80\x80\x80\x80"),
+            (0x7FFFFFFF, b"\xfd\xbf\xbf\xbf\xbf\xbf"),
+        ] {
+            assert_eq!(encode(&[code]), Ok(expected.to_vec()), "code {:#x}", code);
+        }
+
+        assert_eq!(encode(&[72, 233, 0x4E2D]), Ok("Hé中".as_bytes().to_vec()));
+        assert_eq!(encode(&[]), Ok(vec![]));
+        assert_eq!(
+            encode(&[0x80000000]).unwrap_err().message,
+            "bad argument #1 to 'utf8.char' (value out of range)"
+        );
+        assert_eq!(
+            encode(&[1, -1]).unwrap_err().message,
+            "bad argument #2 to 'utf8.char' (value out of range)"
+        );
+    }
+
+    #[test]
+    fn should_count_characters() {
+        let count = |bytes: &[u8], start, end, lax| len(bytes, start, end, lax).unwrap();
+
+        assert_eq!(
+            count("héllo".as_bytes(), None, None, false),
+            Length::Characters(5)
+        );
+        assert_eq!(
+            count("héllo".as_bytes(), Some(-2), None, false),
+            Length::Characters(2)
+        );
+        assert_eq!(
+            count("aé".as_bytes(), Some(1), Some(2), false),
+            Length::Characters(2)
+        );
+        assert_eq!(count(b"", None, None, false), Length::Characters(0));
+        assert_eq!(count(b"abc", Some(4), None, false), Length::Characters(0));
+        assert_eq!(
+            count(b"abc", Some(2), Some(1), false),
+            Length::Characters(0)
+        );
+
+        assert_eq!(
+            count("héllo".as_bytes(), Some(3), None, false),
+            Length::InvalidByte(3)
+        );
+        assert_eq!(
+            count(b"ab\xffcd", None, None, false),
+            Length::InvalidByte(3)
+        );
+        assert_eq!(
+            count(b"\xe4\xbd", None, None, false),
+            Length::InvalidByte(1)
+        );
+        assert_eq!(
+            count(b"\xc0\x80", None, None, false),
+            Length::InvalidByte(1)
+        );
+        assert_eq!(count(b"\x80", None, None, false), Length::InvalidByte(1));
+        assert_eq!(
+            count(b"a\xed\xa0\x80", None, None, false),
+            Length::InvalidByte(2)
+        );
+        assert_eq!(
+            count(b"a\xed\xa0\x80", None, None, true),
+            Length::Characters(2)
+        );
+        assert_eq!(
+            count(b"\xf4\x90\x80\x80", None, None, false),
+            Length::InvalidByte(1)
+        );
+        assert_eq!(
+            count(b"\xf4\x90\x80\x80", None, None, true),
+            Length::Characters(1)
+        );
+        assert_eq!(
+            count(b"\xfd\xbf\xbf\xbf\xbf\xbf", None, None, true),
+            Length::Characters(1)
+        );
+        assert_eq!(
+            count(b"\xfe\x80\x80\x80\x80\x80\x80", None, None, true),
+            Length::InvalidByte(1)
+        );
+
+        for (start, end, message) in [
+            (
+                Some(0),
+                None,
+                "bad argument #2 to 'utf8.len' (initial position out of bounds)",
+            ),
+            (
+                Some(-10),
+                None,
+                "bad argument #2 to 'utf8.len' (initial position out of bounds)",
+            ),
+            (
+                Some(5),
+                None,
+                "bad argument #2 to 'utf8.len' (initial position out of bounds)",
+            ),
+            (
+                Some(1),
+                Some(4),
+                "bad argument #3 to 'utf8.len' (final position out of bounds)",
+            ),
+        ] {
+            assert_eq!(len(b"abc", start, end, false).unwrap_err().message, message);
+        }
+    }
+
+    #[test]
+    fn should_decode_code_points() {
+        assert_eq!(
+            codepoint("héllo".as_bytes(), Some(1), Some(-1), false),
+            Ok(vec![104, 233, 108, 108, 111])
+        );
+        assert_eq!(
+            codepoint("héllo".as_bytes(), Some(2), None, false),
+            Ok(vec![233])
+        );
+        assert_eq!(
+            codepoint("aé".as_bytes(), Some(1), Some(2), false),
+            Ok(vec![97, 233])
+        );
+        assert_eq!(codepoint(b"abc", Some(3), Some(2), false), Ok(vec![]));
+        assert_eq!(codepoint(b"", Some(1), Some(0), false), Ok(vec![]));
+        assert_eq!(
+            codepoint(b"\xed\xa0\x80", Some(1), Some(1), true),
+            Ok(vec![0xD800])
+        );
+
+        for (bytes, start, end, message) in [
+            (
+                b"abc".as_slice(),
+                Some(0),
+                None,
+                "bad argument #2 to 'utf8.codepoint' (out of bounds)",
+            ),
+            (
+                b"abc",
+                Some(1),
+                Some(4),
+                "bad argument #3 to 'utf8.codepoint' (out of bounds)",
+            ),
+            (b"\xff", None, None, "invalid UTF-8 code"),
+            (b"\xed\xa0\x80", None, None, "invalid UTF-8 code"),
+            ("é".as_bytes(), Some(2), None, "invalid UTF-8 code"),
+        ] {
+            assert_eq!(
+                codepoint(bytes, start, end, false).unwrap_err().message,
+                message
+            );
+        }
+    }
+
+    #[test]
+    fn should_find_character_offsets() {
+        let bytes = "aé中x".as_bytes();
+        for (n, start, expected) in [
+            (3, None, Some(4)),
+            (-1, None, Some(7)),
+            (0, Some(3), Some(2)),
+            (5, None, Some(8)),
+            (6, None, None),
+            (-4, None, Some(1)),
+            (-5, None, None),
+            (1, None, Some(1)),
+            (0, None, Some(1)),
+            (-2, Some(4), Some(1)),
+        ] {
+            assert_eq!(
+                offset(bytes, n, start),
+                Ok(expected),
+                "offset({}, {:?})",
+                n,
+                start
+            );
+        }
+
+        assert_eq!(offset(b"abc", 1, Some(4)), Ok(Some(4)));
+        assert_eq!(offset(b"abc", -1, Some(4)), Ok(Some(3)));
+        assert_eq!(offset(b"abc", 2, Some(-1)), Ok(Some(4)));
+        assert_eq!(offset(b"", 1, None), Ok(Some(1)));
+        assert_eq!(offset(b"", -1, None), Ok(None));
+        assert_eq!(offset("aé".as_bytes(), 0, Some(3)), Ok(Some(2)));
+
+        assert_eq!(
+            offset(b"abc", 1, Some(5)).unwrap_err().message,
+            "bad argument #3 to 'utf8.offset' (position out of bounds)"
+        );
+        assert_eq!(
+            offset(b"abc", 1, Some(0)).unwrap_err().message,
+            "bad argument #3 to 'utf8.offset' (position out of bounds)"
+        );
+        assert_eq!(
+            offset("é".as_bytes(), 1, Some(2)).unwrap_err().message,
+            "initial position is a continuation byte"
+        );
+    }
+
+    #[test]
+    fn should_iterate_code_points() {
+        let collect = |bytes: &[u8], lax| {
+            codes(bytes, lax)
+                .and_then(|codes| codes.collect::<Utf8Result<Vec<_>>>())
+                .map_err(|error| error.message)
+        };
+
+        assert_eq!(
+            collect("aé中".as_bytes(), false),
+            Ok(vec![(1, 97), (2, 233), (4, 0x4E2D)])
+        );
+        assert_eq!(collect(b"", false), Ok(vec![]));
+        assert_eq!(
+            collect(b"a\xed\xa0\x80", true),
+            Ok(vec![(1, 97), (2, 0xD800)])
+        );
+        assert_eq!(
+            collect(b"a\xed\xa0\x80", false),
+            Err(String::from("invalid UTF-8 code"))
+        );
+        assert_eq!(
+            collect(b"\x80", false),
+            Err(String::from(
+                "bad argument #1 to 'utf8.codes' (invalid UTF-8 code)"
+            ))
+        );
+        assert_eq!(
+            collect(b"\xc3\xa9\x80", false),
+            Err(String::from("invalid UTF-8 code"))
+        );
+
+        let mut iterator = codes(b"a\xffb", false).unwrap();
+        assert_eq!(iterator.next(), Some(Ok((1, 97))));
+        assert!(iterator.next().unwrap().is_err());
+        assert_eq!(iterator.next(), None);
+    }
+
+    #[test]
+    fn should_split_characters_with_char_pattern() {
+        let characters: Vec<_> = pattern::gmatch("aé中".as_bytes(), CHAR_PATTERN, 0)
+            .map(|found| {
+                let found = found.unwrap();
+                (found.start, found.end)
+            })
+            .collect();
+        assert_eq!(characters, vec![(0, 1), (1, 3), (3, 6)]);
+    }
+}
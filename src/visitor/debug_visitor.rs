@@ -1,8 +1,8 @@
 use crate::{
     ast::{
-        Args, ArgsVisitor, Expression, ExpressionVisitor, Field, FieldVisitor, FunctionCallVisitor,
-        Identifier, Prefix, PrefixVisitor, TableConstructor, TableConstructorVisitor,
-        VariableVisitor,
+        is_right_associative, Args, ArgsVisitor, Constant, Expression, ExpressionVisitor, Field,
+        FieldVisitor, FunctionCallVisitor, Identifier, Prefix, PrefixVisitor, TableConstructor,
+        TableConstructorVisitor, VariableVisitor,
     },
     token::Token,
 };
@@ -10,17 +10,17 @@ use crate::{
 pub struct DebugVisitor;
 
 impl PrefixVisitor<String> for DebugVisitor {
-    fn visit_prefix_group(&self, expression: &Box<Expression>) -> String {
+    fn visit_prefix_group(&self, expression: &Expression) -> String {
         format!("({})", expression.visit(self))
     }
 }
 
 impl FunctionCallVisitor<String> for DebugVisitor {
-    fn visit_function_call(&self, callee: &Box<Prefix>, args: &Args) -> String {
+    fn visit_function_call(&self, callee: &Prefix, args: &Args) -> String {
         format!("[{} a:{}]", callee.visit(self), args.visit(self))
     }
 
-    fn visit_method_call(&self, callee: &Box<Prefix>, method: &Identifier, args: &Args) -> String {
+    fn visit_method_call(&self, callee: &Prefix, method: &Identifier, args: &Args) -> String {
         format!(
             "[{}:{} a:{}]",
             callee.visit(self),
@@ -31,13 +31,13 @@ impl FunctionCallVisitor<String> for DebugVisitor {
 }
 
 impl ArgsVisitor<String> for DebugVisitor {
-    fn visit_args_expression_list(&self, expressions: &Vec<Expression>) -> String {
+    fn visit_args_expression_list(&self, expressions: &[Expression]) -> String {
         let mut expressions_string = String::new();
         for expression in expressions.iter() {
             expressions_string.push_str(&expression.visit(self)[..]);
             expressions_string.push_str(", ")
         }
-        format!("{}", expressions_string)
+        expressions_string
     }
 
     fn visit_args_table_constructor(&self, table_constructor: &TableConstructor) -> String {
@@ -51,17 +51,17 @@ impl ArgsVisitor<String> for DebugVisitor {
 
 impl VariableVisitor<String> for DebugVisitor {
     fn visit_variable_identifier(&self, identifier: &Identifier) -> String {
-        format!("{}", identifier.0.lexeme)
+        identifier.0.lexeme.to_string()
     }
 
-    fn visit_variable_member_access(&self, reference: &Box<Prefix>, member: &Identifier) -> String {
+    fn visit_variable_member_access(&self, reference: &Prefix, member: &Identifier) -> String {
         format!("{}.{}", reference.visit(self), member.0.lexeme)
     }
 
     fn visit_variable_expression_member_access(
         &self,
-        reference: &Box<Prefix>,
-        member: &Box<Expression>,
+        reference: &Prefix,
+        member: &Expression,
     ) -> String {
         format!("{}[{}]", reference.visit(self), member.visit(self))
     }
@@ -82,7 +82,7 @@ impl FieldVisitor<String> for DebugVisitor {
 }
 
 impl TableConstructorVisitor<String> for DebugVisitor {
-    fn visit_fields(&self, fields: &Vec<Field>) -> String {
+    fn visit_fields(&self, fields: &[Field]) -> String {
         let mut fields_string = String::new();
         for field in fields.iter() {
             fields_string.push_str(&field.visit(self)[..]);
@@ -96,21 +96,44 @@ impl ExpressionVisitor<String> for DebugVisitor {
         format!("`{}`", token.lexeme)
     }
 
-    fn visit_unary(&self, operator: &Token, right: &Box<Expression>) -> String {
+    fn visit_constant(&self, constant: &Constant) -> String {
+        match constant {
+            Constant::Nil => String::from("`nil`"),
+            Constant::Boolean(value) => format!("`{}`", value),
+            Constant::Integer(value) => format!("`{}`", value),
+            Constant::Float(value) => format!("`{:?}`", value),
+            Constant::String(value) => format!("`{:?}`", value),
+        }
+    }
+
+    fn visit_unary(&self, operator: &Token, right: &Expression) -> String {
         format!("[{} r={}]", operator.lexeme, right.visit(self))
     }
 
-    fn visit_binary(
-        &self,
-        left: &Box<Expression>,
-        operator: &Token,
-        right: &Box<Expression>,
-    ) -> String {
-        format!(
-            "[{} l={} r={}]",
-            operator.lexeme,
-            left.visit(self),
-            right.visit(self)
-        )
+    fn visit_binary(&self, first: &Expression, rest: &[(&Token, Expression)]) -> String {
+        let mut output = String::new();
+
+        if rest
+            .first()
+            .is_some_and(|(operator, _)| is_right_associative(operator))
+        {
+            let mut left = first;
+            for (operator, right) in rest {
+                output.push_str(&format!("[{} l={} r=", operator.lexeme, left.visit(self)));
+                left = right;
+            }
+            output.push_str(&left.visit(self));
+            output.push_str(&"]".repeat(rest.len()));
+        } else {
+            for (operator, _) in rest.iter().rev() {
+                output.push_str(&format!("[{} l=", operator.lexeme));
+            }
+            output.push_str(&first.visit(self));
+            for (_, right) in rest {
+                output.push_str(&format!(" r={}]", right.visit(self)));
+            }
+        }
+
+        output
     }
 }
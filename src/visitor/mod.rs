@@ -1 +1 @@
-pub mod debug_visitor;
\ No newline at end of file
+pub mod debug_visitor;